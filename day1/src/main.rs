@@ -27,7 +27,7 @@ fn load_input(path: &'static str) -> (Vec<i64>, Vec<i64>) {
                 let mut temp = line
                     .split_whitespace()
                     //Assumption: data is always correct
-                    .map(|f| i64::from_str_radix(f, 10).unwrap());
+                    .map(|f| f.parse::<i64>().unwrap());
 
                 let a = temp.next().unwrap();
                 let b = temp.next().unwrap();
@@ -36,12 +36,11 @@ fn load_input(path: &'static str) -> (Vec<i64>, Vec<i64>) {
             }
             Err(_) => None,
         })
-        .for_each(|e| match e {
-            Some(x) => {
+        .for_each(|e| {
+            if let Some(x) = e {
                 first_list.push(x.0);
                 second_list.push(x.1);
             }
-            None => {}
         });
 
     first_list.sort();
@@ -0,0 +1,149 @@
+use std::{collections::BTreeMap, fmt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnsafeReason {
+    StepTooLarge { position: usize },
+    StepTooSmall { position: usize },
+    ZeroStep { position: usize },
+    DirectionChange { position: usize },
+}
+
+impl UnsafeReason {
+    pub fn position(&self) -> usize {
+        match *self {
+            UnsafeReason::StepTooLarge { position }
+            | UnsafeReason::StepTooSmall { position }
+            | UnsafeReason::ZeroStep { position }
+            | UnsafeReason::DirectionChange { position } => position,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            UnsafeReason::StepTooLarge { .. } => "StepTooLarge",
+            UnsafeReason::StepTooSmall { .. } => "StepTooSmall",
+            UnsafeReason::ZeroStep { .. } => "ZeroStep",
+            UnsafeReason::DirectionChange { .. } => "DirectionChange",
+        }
+    }
+}
+
+impl fmt::Display for UnsafeReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.name(), self.position())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Classification {
+    Safe,
+    SafeAfterRemoving(Vec<usize>),
+    Unsafe(UnsafeReason),
+}
+
+impl Classification {
+    pub fn is_safe(&self) -> bool {
+        !matches!(self, Classification::Unsafe(_))
+    }
+}
+
+impl fmt::Display for Classification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Classification::Safe => write!(f, "Safe"),
+            Classification::SafeAfterRemoving(indices) => {
+                write!(f, "SafeAfterRemoving({:?})", indices)
+            }
+            Classification::Unsafe(reason) => write!(f, "Unsafe({})", reason),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Summary {
+    safe: usize,
+    safe_after_removing: usize,
+    unsafe_reasons: BTreeMap<&'static str, usize>,
+}
+
+impl Summary {
+    pub fn new(classifications: &[Classification]) -> Self {
+        let mut summary = Summary::default();
+
+        for classification in classifications {
+            match classification {
+                Classification::Safe => summary.safe += 1,
+                Classification::SafeAfterRemoving(_) => summary.safe_after_removing += 1,
+                Classification::Unsafe(reason) => {
+                    *summary.unsafe_reasons.entry(reason.name()).or_default() += 1
+                }
+            }
+        }
+
+        summary
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Safe: {}", self.safe)?;
+        writeln!(f, "Safe after removing: {}", self.safe_after_removing)?;
+        writeln!(f, "Unsafe: {}", self.unsafe_reasons.values().sum::<usize>())?;
+
+        let width = self
+            .unsafe_reasons
+            .keys()
+            .map(|name| name.len())
+            .max()
+            .unwrap_or(0);
+        let largest = self.unsafe_reasons.values().max().copied().unwrap_or(0);
+
+        for (name, &count) in &self.unsafe_reasons {
+            // Scale bars so the largest bucket is at most 50 characters wide
+            let bar = if largest > 50 {
+                count * 50 / largest
+            } else {
+                count
+            };
+
+            writeln!(
+                f,
+                "  {:width$} {:>6} {}",
+                name,
+                count,
+                "#".repeat(bar),
+                width = width
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_histogram() {
+        let mut classifications = vec![
+            Classification::Safe,
+            Classification::SafeAfterRemoving(vec![2]),
+            Classification::Unsafe(UnsafeReason::ZeroStep { position: 0 }),
+        ];
+        classifications.extend(
+            (0..100)
+                .map(|position| Classification::Unsafe(UnsafeReason::StepTooLarge { position })),
+        );
+
+        let summary = Summary::new(&classifications).to_string();
+
+        assert_eq!(
+            summary,
+            format!(
+                "Safe: 1\nSafe after removing: 1\nUnsafe: 101\n  StepTooLarge    100 {}\n  ZeroStep          1 \n",
+                "#".repeat(50)
+            )
+        );
+    }
+}
@@ -1,8 +1,13 @@
+mod diagnostics;
+mod policy;
 use std::{
     fs::File,
     io::{BufRead, BufReader},
 };
 
+use diagnostics::{Classification, Summary};
+use policy::{Direction, SafetyPolicy};
+
 fn load_input(path: &'static str) -> Vec<Vec<u64>> {
     let file = match File::open(path) {
         Ok(x) => x,
@@ -16,67 +21,113 @@ fn load_input(path: &'static str) -> Vec<Vec<u64>> {
 
     let file_reader = BufReader::new(file);
 
-    file_reader
-        .lines()
-        .filter_map(|line_res| match line_res {
-            Ok(line) => {
-                Some(
-                    line.split_whitespace()
-                        //Assumption: data is always correct
-                        .map(|level| u64::from_str_radix(level, 10).unwrap())
-                        .collect::<Vec<u64>>(),
-                )
+    let (reports, warnings) = parse_reports(file_reader.lines().map_while(Result::ok));
+
+    for warning in warnings {
+        eprintln!("Warning: {}", warning);
+    }
+
+    reports
+}
+
+// Blank lines and lines with unparsable levels are skipped. Reports with fewer
+// than two levels are kept: they have no adjacent pair that could be unsafe,
+// so they count as safe under both parts.
+fn parse_reports(lines: impl Iterator<Item = String>) -> (Vec<Vec<u64>>, Vec<String>) {
+    let mut reports: Vec<Vec<u64>> = Vec::new();
+    let mut warnings: Vec<String> = Vec::new();
+
+    for (index, line) in lines.enumerate() {
+        let line_number = index + 1;
+
+        let levels: Result<Vec<u64>, _> = line.split_whitespace().map(str::parse::<u64>).collect();
+
+        match levels {
+            Ok(levels) if levels.is_empty() => {
+                warnings.push(format!("line {}: blank line skipped", line_number));
+            }
+            Ok(levels) => {
+                if levels.len() == 1 {
+                    warnings.push(format!(
+                        "line {}: report has a single level and is trivially safe",
+                        line_number
+                    ));
+                }
+
+                reports.push(levels);
+            }
+            Err(err) => {
+                warnings.push(format!("line {}: report skipped ({})", line_number, err));
             }
-            Err(_) => None,
-        })
-        .collect()
+        }
+    }
+
+    (reports, warnings)
 }
 
 fn part1(reports: &[Vec<u64>]) -> u64 {
-    reports
-        .iter()
-        .map(|levels| {
-            let increasing = levels[0] < levels[1];
-            levels.iter().zip(levels.iter().skip(1)).any(|(&a, &b)| {
-                let diff = a.abs_diff(b);
-                diff > 3 || diff == 0 || (a < b) != increasing
-            })
-        })
-        .filter(|&e| !e)
-        .count() as u64
+    count_safe(reports, &SafetyPolicy::new())
 }
 
 fn part2(reports: &[Vec<u64>]) -> u64 {
+    count_safe(reports, &SafetyPolicy::new().with_max_removals(1))
+}
+
+fn count_safe(reports: &[Vec<u64>], policy: &SafetyPolicy) -> u64 {
     reports
         .iter()
-        .map(|levels| {
-            let mut values: Vec<bool> = Vec::new();
+        .filter(|levels| policy.classify(levels).is_safe())
+        .count() as u64
+}
 
-            let increasing = levels[0] < levels[1];
-            let value = levels.iter().zip(levels.iter().skip(1)).any(|(&a, &b)| {
-                let diff = a.abs_diff(b);
-                diff > 3 || diff == 0 || (a < b) != increasing
-            });
+fn print_diagnostics(reports: &[Vec<u64>], policy: &SafetyPolicy) {
+    let classifications: Vec<Classification> = reports
+        .iter()
+        .map(|levels| policy.classify(levels))
+        .collect();
 
-            values.push(value);
+    for (index, (levels, classification)) in reports.iter().zip(&classifications).enumerate() {
+        println!("Report {}: {:?} -> {}", index + 1, levels, classification);
+    }
 
-            for i in 0..levels.len() {
-                let mut temp = levels.clone();
-                temp.remove(i);
+    print!("{}", Summary::new(&classifications));
+}
 
-                let increasing = temp[0] < temp[1];
-                let value = temp.iter().zip(temp.iter().skip(1)).any(|(&a, &b)| {
-                    let diff = a.abs_diff(b);
-                    diff > 3 || diff == 0 || (a < b) != increasing
-                });
+fn parse_policy(args: &[String]) -> Option<SafetyPolicy> {
+    let mut policy = SafetyPolicy::new();
+    let mut custom = false;
 
-                values.push(value);
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--steps" => {
+                let value = it.next().expect("--steps expects <min>-<max>");
+                let (min, max) = value.split_once('-').expect("--steps expects <min>-<max>");
+                policy = policy.with_step_range(min.parse().unwrap(), max.parse().unwrap());
             }
+            "--direction" => {
+                let direction = match it.next().map(String::as_str) {
+                    Some("increasing") => Direction::Increasing,
+                    Some("decreasing") => Direction::Decreasing,
+                    Some("either") => Direction::Either,
+                    _ => panic!("--direction expects increasing, decreasing or either"),
+                };
+                policy = policy.with_direction(direction);
+            }
+            "--removals" => {
+                let value = it.next().expect("--removals expects a number");
+                policy = policy.with_max_removals(value.parse().unwrap());
+            }
+            _ => continue,
+        }
+        custom = true;
+    }
 
-            values.iter().any(|&e| !e)
-        })
-        .filter(|&e| e)
-        .count() as u64
+    if custom {
+        Some(policy)
+    } else {
+        None
+    }
 }
 
 fn main() {
@@ -84,4 +135,84 @@ fn main() {
 
     println!("Part 1: {}", part1(&reports));
     println!("Part 2: {}", part2(&reports));
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let custom_policy = parse_policy(&args);
+    if let Some(policy) = &custom_policy {
+        println!("Custom: {}", count_safe(&reports, policy));
+    }
+
+    if args.iter().any(|arg| arg == "--diagnostics") {
+        let policy = custom_policy.unwrap_or(SafetyPolicy::new().with_max_removals(1));
+        print_diagnostics(&reports, &policy);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> (Vec<Vec<u64>>, Vec<String>) {
+        parse_reports(input.lines().map(String::from))
+    }
+
+    #[test]
+    fn empty_report_is_safe() {
+        let reports = vec![vec![]];
+
+        assert_eq!(part1(&reports), 1);
+        assert_eq!(part2(&reports), 1);
+    }
+
+    #[test]
+    fn single_level_report_is_safe() {
+        let reports = vec![vec![5]];
+
+        assert_eq!(part1(&reports), 1);
+        assert_eq!(part2(&reports), 1);
+    }
+
+    #[test]
+    fn two_level_reports() {
+        let valid = vec![vec![1, 3]];
+        let zero_step = vec![vec![4, 4]];
+        let large_step = vec![vec![1, 9]];
+
+        assert_eq!(part1(&valid), 1);
+        assert_eq!(part1(&zero_step), 0);
+        assert_eq!(part1(&large_step), 0);
+
+        // Removing either level leaves a single, trivially safe level
+        assert_eq!(part2(&valid), 1);
+        assert_eq!(part2(&zero_step), 1);
+        assert_eq!(part2(&large_step), 1);
+    }
+
+    #[test]
+    fn blank_lines_are_skipped_with_warning() {
+        let (reports, warnings) = parse("7 6 4 2 1\n\n1 3 6 7 9\n   \n");
+
+        assert_eq!(reports, vec![vec![7, 6, 4, 2, 1], vec![1, 3, 6, 7, 9]]);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("line 2:"));
+        assert!(warnings[1].starts_with("line 4:"));
+    }
+
+    #[test]
+    fn short_reports_are_kept_with_warning() {
+        let (reports, warnings) = parse("5\n1 2");
+
+        assert_eq!(reports, vec![vec![5], vec![1, 2]]);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("line 1:"));
+    }
+
+    #[test]
+    fn unparsable_reports_are_skipped_with_warning() {
+        let (reports, warnings) = parse("1 2 x\n1 2 3");
+
+        assert_eq!(reports, vec![vec![1, 2, 3]]);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("line 1:"));
+    }
 }
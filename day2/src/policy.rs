@@ -0,0 +1,330 @@
+use crate::diagnostics::{Classification, UnsafeReason};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Increasing,
+    Decreasing,
+    Either,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SafetyPolicy {
+    min_step: u64,
+    max_step: u64,
+    direction: Direction,
+    max_removals: usize,
+}
+
+impl Default for SafetyPolicy {
+    fn default() -> Self {
+        Self {
+            min_step: 1,
+            max_step: 3,
+            direction: Direction::Either,
+            max_removals: 0,
+        }
+    }
+}
+
+impl SafetyPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_step_range(mut self, min_step: u64, max_step: u64) -> Self {
+        self.min_step = min_step;
+        self.max_step = max_step;
+        self
+    }
+
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn with_max_removals(mut self, max_removals: usize) -> Self {
+        self.max_removals = max_removals;
+        self
+    }
+
+    pub fn classify(&self, levels: &[u64]) -> Classification {
+        match self.removals(levels) {
+            Some(removed) if removed.is_empty() => Classification::Safe,
+            Some(removed) => Classification::SafeAfterRemoving(removed),
+            None => Classification::Unsafe(self.first_violation(levels)),
+        }
+    }
+
+    // The reason is taken from the unmodified report. With either direction allowed,
+    // the direction that stays valid the longest is blamed.
+    fn first_violation(&self, levels: &[u64]) -> UnsafeReason {
+        let directions = match self.direction {
+            Direction::Increasing => vec![true],
+            Direction::Decreasing => vec![false],
+            Direction::Either => vec![true, false],
+        };
+
+        directions
+            .into_iter()
+            .filter_map(|increasing| self.first_violation_in_direction(levels, increasing))
+            .max_by_key(|reason| reason.position())
+            .expect("an unsafe report has at least one violation")
+    }
+
+    fn first_violation_in_direction(
+        &self,
+        levels: &[u64],
+        increasing: bool,
+    ) -> Option<UnsafeReason> {
+        levels
+            .iter()
+            .zip(levels.iter().skip(1))
+            .enumerate()
+            .find_map(|(position, (&a, &b))| {
+                let diff = a.abs_diff(b);
+
+                if diff == 0 && self.min_step > 0 {
+                    Some(UnsafeReason::ZeroStep { position })
+                } else if diff < self.min_step {
+                    Some(UnsafeReason::StepTooSmall { position })
+                } else if diff > self.max_step {
+                    Some(UnsafeReason::StepTooLarge { position })
+                } else if diff != 0 && (a < b) != increasing {
+                    Some(UnsafeReason::DirectionChange { position })
+                } else {
+                    None
+                }
+            })
+    }
+
+    /// Smallest set of level indices that has to be removed for the report to be safe,
+    /// or `None` when that takes more removals than the policy tolerates.
+    pub fn removals(&self, levels: &[u64]) -> Option<Vec<usize>> {
+        let candidates = match self.direction {
+            Direction::Increasing => vec![self.removals_in_direction(levels, true)],
+            Direction::Decreasing => vec![self.removals_in_direction(levels, false)],
+            Direction::Either => vec![
+                self.removals_in_direction(levels, true),
+                self.removals_in_direction(levels, false),
+            ],
+        };
+
+        candidates
+            .into_iter()
+            .flatten()
+            .min_by_key(|removed| removed.len())
+    }
+
+    fn step_allowed(&self, a: u64, b: u64, increasing: bool) -> bool {
+        let diff = a.abs_diff(b);
+        diff >= self.min_step && diff <= self.max_step && (diff == 0 || (a < b) == increasing)
+    }
+
+    fn removals_in_direction(&self, levels: &[u64], increasing: bool) -> Option<Vec<usize>> {
+        if levels.is_empty() {
+            return Some(Vec::new());
+        }
+
+        let k = self.max_removals;
+
+        // cost[i]: fewest removals among levels[..=i] so that the kept levels end with levels[i]
+        // previous[i]: the kept level before levels[i] in that solution
+        let mut cost: Vec<Option<usize>> = vec![None; levels.len()];
+        let mut previous: Vec<Option<usize>> = vec![None; levels.len()];
+
+        for i in 0..levels.len() {
+            if i <= k {
+                cost[i] = Some(i);
+            }
+
+            // Skipping more than k levels can never stay within the budget
+            for j in i.saturating_sub(k + 1)..i {
+                let Some(cost_j) = cost[j] else {
+                    continue;
+                };
+
+                if !self.step_allowed(levels[j], levels[i], increasing) {
+                    continue;
+                }
+
+                let candidate = cost_j + (i - j - 1);
+                if candidate <= k && cost[i].is_none_or(|current| candidate < current) {
+                    cost[i] = Some(candidate);
+                    previous[i] = Some(j);
+                }
+            }
+        }
+
+        let (last, _) = cost
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| c.map(|c| (i, c + (levels.len() - 1 - i))))
+            .filter(|&(_, total)| total <= k)
+            .min_by_key(|&(_, total)| total)?;
+
+        let mut kept = vec![false; levels.len()];
+        let mut current = Some(last);
+        while let Some(i) = current {
+            kept[i] = true;
+            current = previous[i];
+        }
+
+        Some(
+            kept.iter()
+                .enumerate()
+                .filter_map(|(i, &kept)| if kept { None } else { Some(i) })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_safe(policy: &SafetyPolicy, levels: &[u64]) -> bool {
+        let steps = || levels.iter().zip(levels.iter().skip(1));
+
+        match policy.direction {
+            Direction::Increasing => steps().all(|(&a, &b)| policy.step_allowed(a, b, true)),
+            Direction::Decreasing => steps().all(|(&a, &b)| policy.step_allowed(a, b, false)),
+            Direction::Either => [true, false]
+                .into_iter()
+                .any(|increasing| steps().all(|(&a, &b)| policy.step_allowed(a, b, increasing))),
+        }
+    }
+
+    // Tries every set of removed indices, smallest sets first
+    fn fewest_removals(policy: &SafetyPolicy, levels: &[u64]) -> Option<usize> {
+        (0u32..1 << levels.len())
+            .filter(|mask| mask.count_ones() as usize <= policy.max_removals)
+            .filter(|mask| {
+                let kept: Vec<u64> = (0..levels.len())
+                    .filter(|i| mask & 1 << i == 0)
+                    .map(|i| levels[i])
+                    .collect();
+                is_safe(policy, &kept)
+            })
+            .map(|mask| mask.count_ones() as usize)
+            .min()
+    }
+
+    // Every report of up to five levels between 0 and 5
+    fn reports() -> Vec<Vec<u64>> {
+        let mut reports: Vec<Vec<u64>> = vec![vec![]];
+        let mut current: Vec<Vec<u64>> = vec![vec![]];
+
+        for _ in 0..5 {
+            current = current
+                .iter()
+                .flat_map(|report| {
+                    (0..6).map(move |level| {
+                        let mut report = report.clone();
+                        report.push(level);
+                        report
+                    })
+                })
+                .collect();
+            reports.extend(current.iter().cloned());
+        }
+
+        reports
+    }
+
+    #[test]
+    fn removals_are_minimal() {
+        let policies = [
+            SafetyPolicy::new(),
+            SafetyPolicy::new().with_max_removals(1),
+            SafetyPolicy::new().with_max_removals(2),
+            SafetyPolicy::new()
+                .with_step_range(0, 2)
+                .with_max_removals(1),
+            SafetyPolicy::new()
+                .with_direction(Direction::Increasing)
+                .with_max_removals(1),
+            SafetyPolicy::new()
+                .with_direction(Direction::Decreasing)
+                .with_step_range(2, 4)
+                .with_max_removals(2),
+        ];
+
+        for policy in &policies {
+            for levels in reports() {
+                let removed = policy.removals(&levels);
+
+                assert_eq!(
+                    removed.as_ref().map(Vec::len),
+                    fewest_removals(policy, &levels),
+                    "{:?} {:?}",
+                    policy,
+                    levels
+                );
+
+                if let Some(removed) = removed {
+                    let kept: Vec<u64> = (0..levels.len())
+                        .filter(|i| !removed.contains(i))
+                        .map(|i| levels[i])
+                        .collect();
+                    assert!(is_safe(policy, &kept), "{:?} {:?}", policy, levels);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn zero_steps_need_a_zero_minimum() {
+        let strict = SafetyPolicy::new();
+        let relaxed = SafetyPolicy::new().with_step_range(0, 3);
+
+        assert_eq!(strict.removals(&[1, 1, 2]), None);
+        assert_eq!(relaxed.removals(&[1, 1, 2]), Some(vec![]));
+        assert_eq!(relaxed.removals(&[3, 3, 2]), Some(vec![]));
+        assert_eq!(
+            relaxed
+                .with_direction(Direction::Increasing)
+                .removals(&[3, 3, 2]),
+            None
+        );
+    }
+
+    #[test]
+    fn classification() {
+        let policy = SafetyPolicy::new().with_max_removals(1);
+
+        assert_eq!(policy.classify(&[7, 6, 4, 2, 1]), Classification::Safe);
+        assert_eq!(
+            policy.classify(&[1, 9, 2, 3]),
+            Classification::SafeAfterRemoving(vec![1])
+        );
+        assert_eq!(
+            policy.classify(&[1, 2, 7, 8, 9]),
+            Classification::Unsafe(UnsafeReason::StepTooLarge { position: 1 })
+        );
+        assert_eq!(
+            policy.classify(&[8, 6, 4, 4, 4]),
+            Classification::Unsafe(UnsafeReason::ZeroStep { position: 2 })
+        );
+    }
+
+    #[test]
+    fn first_violation_blames_longest_valid_direction() {
+        let policy = SafetyPolicy::new();
+
+        // Increasing fails at the first step, decreasing only at the third
+        assert_eq!(
+            policy.first_violation(&[5, 4, 3, 6]),
+            UnsafeReason::DirectionChange { position: 2 }
+        );
+        assert_eq!(
+            policy
+                .with_direction(Direction::Increasing)
+                .first_violation(&[5, 4, 3, 6]),
+            UnsafeReason::DirectionChange { position: 0 }
+        );
+        assert_eq!(
+            policy.with_step_range(2, 3).first_violation(&[1, 3, 4]),
+            UnsafeReason::StepTooSmall { position: 1 }
+        );
+    }
+}
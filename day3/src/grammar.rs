@@ -0,0 +1,282 @@
+use matcher::{Matcher, Pattern, Restart, Token};
+
+use crate::{Instruction, Op};
+
+// Every number of up to 18 digits fits in an i64
+const MAX_DIGITS: usize = 18;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Grammar {
+    max_digits: usize,
+    signed: bool,
+    whitespace: bool,
+    case_sensitive: bool,
+    extended: bool,
+    restart: Restart,
+}
+
+impl Grammar {
+    // The grammar of the puzzle: `mul(X,Y)` with 1-3 digit unsigned operands, exactly as
+    // written. Like the original hand-written parser, a partial match swallows the byte
+    // it fails on, so `mmul(2,3)`, `mumul(5,6)` and `mul(mul(2,3),4)` hold nothing
+    pub fn strict() -> Self {
+        Self {
+            max_digits: 3,
+            signed: false,
+            whitespace: false,
+            case_sensitive: true,
+            extended: false,
+            restart: Restart::AfterAttempt,
+        }
+    }
+
+    pub fn lenient() -> Self {
+        Self {
+            max_digits: 9,
+            signed: true,
+            whitespace: true,
+            case_sensitive: false,
+            extended: false,
+            restart: Restart::EveryByte,
+        }
+    }
+
+    pub fn with_max_digits(mut self, max_digits: usize) -> Self {
+        assert!(
+            (1..=MAX_DIGITS).contains(&max_digits),
+            "max digits must be between 1 and {}",
+            MAX_DIGITS
+        );
+
+        self.max_digits = max_digits;
+        self
+    }
+
+    // Restart::EveryByte finds what the puzzle's regular expression would, including
+    // instructions that start inside a partial one
+    pub fn with_restart(mut self, restart: Restart) -> Self {
+        self.restart = restart;
+        self
+    }
+
+    // add(), sub() and reset() are opt-in so that the puzzle answers stay unaffected
+    // by whatever garbage happens to look like them
+    pub fn with_extended(mut self, extended: bool) -> Self {
+        self.extended = extended;
+        self
+    }
+
+    pub fn instructions(&self) -> Matcher<Op> {
+        let mut patterns = vec![
+            Pattern::new(self.call("mul", 2), |args| Op::Mul {
+                x: args[0],
+                y: args[1],
+            }),
+            Pattern::new(self.call("do", 0), |_| Op::Do),
+            Pattern::new(self.call("don't", 0), |_| Op::Dont),
+        ];
+
+        if self.extended {
+            patterns.extend([
+                Pattern::new(self.call("add", 2), |args| Op::Add {
+                    x: args[0],
+                    y: args[1],
+                }),
+                Pattern::new(self.call("sub", 2), |args| Op::Sub {
+                    x: args[0],
+                    y: args[1],
+                }),
+                Pattern::new(self.call("reset", 0), |_| Op::Reset),
+            ]);
+        }
+
+        Matcher::new(patterns).with_restart(self.restart)
+    }
+
+    fn call(&self, name: &'static str, arity: usize) -> Vec<Token> {
+        let mut tokens = vec![self.literal(name), Token::Literal("(")];
+        self.space(&mut tokens);
+
+        for i in 0..arity {
+            if i > 0 {
+                tokens.push(Token::Literal(","));
+                self.space(&mut tokens);
+            }
+
+            tokens.push(Token::Number {
+                max_digits: self.max_digits,
+                signed: self.signed,
+            });
+            self.space(&mut tokens);
+        }
+
+        tokens.push(Token::Literal(")"));
+
+        tokens
+    }
+
+    fn literal(&self, literal: &'static str) -> Token {
+        if self.case_sensitive {
+            Token::Literal(literal)
+        } else {
+            Token::CaselessLiteral(literal)
+        }
+    }
+
+    fn space(&self, tokens: &mut Vec<Token>) {
+        if self.whitespace {
+            tokens.push(Token::Whitespace);
+        }
+    }
+}
+
+// Instructions found by the lenient grammar that the strict one did not find at the
+// same place. Both are in input order, so one pass over each is enough
+pub fn extra_matches<'a>(
+    strict: &[Instruction],
+    lenient: &'a [Instruction],
+) -> Vec<&'a Instruction> {
+    let mut strict = strict.iter().peekable();
+
+    lenient
+        .iter()
+        .filter(|instruction| {
+            while strict
+                .next_if(|found| found.span.start < instruction.span.start)
+                .is_some()
+            {}
+
+            strict.peek() != Some(instruction)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ops(grammar: Grammar, input: &[u8]) -> Vec<Op> {
+        grammar
+            .instructions()
+            .find_all(input)
+            .into_iter()
+            .map(|instruction| instruction.value)
+            .collect()
+    }
+
+    #[test]
+    fn strict_matches_the_original_parser() {
+        // The original parser found nothing in any of these
+        for input in [
+            &b"mmul(2,3)"[..],
+            b"mumul(5,6)",
+            b"mul(mul(2,3),4)",
+            b"mul(12,3mul(4,5)",
+            b"ddo()",
+            b"dodon't()",
+        ] {
+            assert!(
+                ops(Grammar::strict(), input).is_empty(),
+                "{}",
+                String::from_utf8_lossy(input)
+            );
+        }
+
+        assert_eq!(
+            ops(Grammar::strict(), b"ddo()don't()mul(1,1)"),
+            vec![Op::Dont, Op::Mul { x: 1, y: 1 }]
+        );
+        assert_eq!(
+            ops(Grammar::strict(), b"mul(1,2)mul(3,4)do()mul(5,6)"),
+            vec![
+                Op::Mul { x: 1, y: 2 },
+                Op::Mul { x: 3, y: 4 },
+                Op::Do,
+                Op::Mul { x: 5, y: 6 }
+            ]
+        );
+    }
+
+    #[test]
+    fn restarting_on_every_byte_finds_inner_instructions() {
+        let grammar = Grammar::strict().with_restart(Restart::EveryByte);
+
+        assert_eq!(ops(grammar, b"mmul(2,3)"), vec![Op::Mul { x: 2, y: 3 }]);
+        assert_eq!(
+            ops(grammar, b"mul(mul(2,3),4)"),
+            vec![Op::Mul { x: 2, y: 3 }]
+        );
+        assert_eq!(ops(grammar, b"ddo()"), vec![Op::Do]);
+    }
+
+    #[test]
+    #[should_panic(expected = "max digits must be between 1 and 18")]
+    fn max_digits_fit_in_an_i64() {
+        Grammar::lenient().with_max_digits(19);
+    }
+
+    #[test]
+    fn lenient_accepts_more() {
+        let input = b"MUL( 2 , -3 )mul(1234,5)Don't()mul(+7,8)";
+
+        assert!(ops(Grammar::strict(), input).is_empty());
+        assert_eq!(
+            ops(Grammar::lenient(), input),
+            vec![
+                Op::Mul { x: 2, y: -3 },
+                Op::Mul { x: 1234, y: 5 },
+                Op::Dont,
+                Op::Mul { x: 7, y: 8 }
+            ]
+        );
+        assert_eq!(
+            ops(Grammar::lenient().with_max_digits(3), input),
+            vec![Op::Mul { x: 2, y: -3 }, Op::Dont, Op::Mul { x: 7, y: 8 }]
+        );
+    }
+
+    #[test]
+    fn extended_instructions_are_opt_in() {
+        let input = b"add(1,2)sub(3,4)reset()";
+
+        assert!(ops(Grammar::strict(), input).is_empty());
+        assert_eq!(
+            ops(Grammar::strict().with_extended(true), input),
+            vec![Op::Add { x: 1, y: 2 }, Op::Sub { x: 3, y: 4 }, Op::Reset]
+        );
+    }
+
+    #[test]
+    fn extra_matches_are_lenient_only() {
+        let input = b"mul(1,2) mul( 3,4) mul(5,6) MUL(7,8)";
+
+        let strict = Grammar::strict().instructions().find_all(input);
+        let lenient = Grammar::lenient().instructions().find_all(input);
+
+        let extra: Vec<(Op, std::ops::Range<usize>)> = extra_matches(&strict, &lenient)
+            .into_iter()
+            .map(|instruction| (instruction.value, instruction.span.clone()))
+            .collect();
+        assert_eq!(
+            extra,
+            vec![
+                (Op::Mul { x: 3, y: 4 }, 9..18),
+                (Op::Mul { x: 7, y: 8 }, 28..36)
+            ]
+        );
+
+        // Same place but a different operand, and the same operands one byte later
+        let input = b"mul(1,23)4 mmul(5,6)";
+        let strict = Grammar::strict().instructions().find_all(input);
+        let lenient = Grammar::lenient()
+            .with_max_digits(1)
+            .instructions()
+            .find_all(input);
+        assert_eq!(strict.len(), 1);
+        let extra: Vec<std::ops::Range<usize>> = extra_matches(&strict, &lenient)
+            .into_iter()
+            .map(|instruction| instruction.span.clone())
+            .collect();
+        assert_eq!(extra, vec![12..20]);
+    }
+}
@@ -0,0 +1,117 @@
+use std::io::{self, Write};
+
+use crate::{machine::Machine, Instruction, Op};
+
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+// Colours of instructions that the machine would execute or skip
+const ENABLED: &str = "\x1b[1;32m";
+const DISABLED: &str = "\x1b[1;31m";
+const TOGGLE: &str = "\x1b[1;36m";
+
+pub fn highlight(
+    out: &mut impl Write,
+    data: &[u8],
+    instructions: &[Instruction],
+    mut machine: Machine,
+) -> io::Result<()> {
+    let mut position = 0;
+
+    for instruction in instructions {
+        let span = instruction.span.clone();
+
+        write_garbage(out, &data[position..span.start])?;
+
+        let colour = match instruction.value {
+            Op::Do | Op::Dont => TOGGLE,
+            _ if machine.enabled() => ENABLED,
+            _ => DISABLED,
+        };
+        machine.step(instruction);
+
+        write!(out, "{}", colour)?;
+        out.write_all(&data[span.clone()])?;
+        write!(out, "{}", RESET)?;
+
+        position = span.end;
+    }
+
+    write_garbage(out, &data[position..])?;
+    writeln!(out)
+}
+
+// Garbage may contain anything, so control characters and invalid UTF-8 are
+// escaped rather than sent to the terminal as is
+fn write_garbage(out: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    if bytes.is_empty() {
+        return Ok(());
+    }
+
+    write!(out, "{}", DIM)?;
+
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if c.is_control() && c != '\n' && c != '\t' {
+                write!(out, "{}", c.escape_default())?;
+            } else {
+                write!(out, "{}", c)?;
+            }
+        }
+
+        for byte in chunk.invalid() {
+            write!(out, "\\x{:02x}", byte)?;
+        }
+    }
+
+    write!(out, "{}", RESET)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{grammar::Grammar, machine::Toggles};
+
+    fn render(data: &[u8]) -> String {
+        let instructions = Grammar::strict().instructions().find_all(data);
+
+        let mut out: Vec<u8> = Vec::new();
+        highlight(&mut out, data, &instructions, Machine::new(Toggles::Flat)).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn instructions_are_coloured_by_state() {
+        assert_eq!(
+            render(b"mul(1,2)\xff\x00don't()mul(3,4)do()"),
+            [
+                ENABLED,
+                "mul(1,2)",
+                RESET,
+                DIM,
+                "\\xff\\u{0}",
+                RESET,
+                TOGGLE,
+                "don't()",
+                RESET,
+                DISABLED,
+                "mul(3,4)",
+                RESET,
+                TOGGLE,
+                "do()",
+                RESET,
+                "\n",
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn garbage_is_dimmed_and_escaped() {
+        assert_eq!(
+            render(b"a\tb\nc\x1b\xe2\x82"),
+            [DIM, "a\tb\nc\\u{1b}\\xe2\\x82", RESET, "\n"].concat()
+        );
+        assert_eq!(render(b""), "\n");
+        assert_eq!(render("é!".as_bytes()), [DIM, "é!", RESET, "\n"].concat());
+    }
+}
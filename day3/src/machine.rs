@@ -0,0 +1,233 @@
+use std::fmt;
+
+use crate::{Instruction, Op};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Toggles {
+    // do() and don't() have no effect
+    Ignored,
+    // do() enables and don't() disables, regardless of what came before
+    Flat,
+    // don't() opens a disabled scope and do() closes the innermost one
+    Nested,
+}
+
+#[derive(Debug, Clone)]
+pub struct TraceEntry {
+    pub counter: usize,
+    pub instruction: Instruction,
+    pub executed: bool,
+    pub accumulator: i64,
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{:<5} {:>7}..{:<7} {:<24} {:<8} acc={}",
+            self.counter,
+            self.instruction.span.start,
+            self.instruction.span.end,
+            format!("{:?}", self.instruction.value),
+            if self.executed { "executed" } else { "skipped" },
+            self.accumulator
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct Machine {
+    toggles: Toggles,
+    disabled_depth: usize,
+    accumulator: i64,
+    counter: usize,
+    trace: Option<Vec<TraceEntry>>,
+}
+
+impl Machine {
+    pub fn new(toggles: Toggles) -> Self {
+        Self {
+            toggles,
+            disabled_depth: 0,
+            accumulator: 0,
+            counter: 0,
+            trace: None,
+        }
+    }
+
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(Vec::new());
+        self
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.disabled_depth == 0
+    }
+
+    pub fn trace(&self) -> &[TraceEntry] {
+        self.trace.as_deref().unwrap_or_default()
+    }
+
+    pub fn run(&mut self, instructions: &[Instruction]) -> i64 {
+        instructions
+            .iter()
+            .for_each(|instruction| self.step(instruction));
+
+        self.accumulator
+    }
+
+    pub fn step(&mut self, instruction: &Instruction) {
+        let executed = match instruction.value {
+            Op::Do | Op::Dont => {
+                self.toggle(instruction.value);
+                self.toggles != Toggles::Ignored
+            }
+            _ if !self.enabled() => false,
+            // Arithmetic wraps around, so operands of any size give one defined answer
+            // in debug and release builds alike
+            Op::Mul { x, y } => self.execute(self.accumulator.wrapping_add(x.wrapping_mul(y))),
+            Op::Add { x, y } => self.execute(self.accumulator.wrapping_add(x.wrapping_add(y))),
+            Op::Sub { x, y } => self.execute(self.accumulator.wrapping_add(x.wrapping_sub(y))),
+            Op::Reset => self.execute(0),
+        };
+
+        self.counter += 1;
+
+        if let Some(trace) = &mut self.trace {
+            trace.push(TraceEntry {
+                counter: self.counter,
+                instruction: instruction.clone(),
+                executed,
+                accumulator: self.accumulator,
+            });
+        }
+    }
+
+    fn execute(&mut self, accumulator: i64) -> bool {
+        self.accumulator = accumulator;
+        true
+    }
+
+    fn toggle(&mut self, op: Op) {
+        match (self.toggles, op) {
+            (Toggles::Ignored, _) => {}
+            (Toggles::Flat, Op::Do) => self.disabled_depth = 0,
+            (Toggles::Flat, _) => self.disabled_depth = 1,
+            (Toggles::Nested, Op::Do) => {
+                self.disabled_depth = self.disabled_depth.saturating_sub(1)
+            }
+            (Toggles::Nested, _) => self.disabled_depth += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use matcher::Spanned;
+
+    use super::*;
+
+    fn program(ops: &[Op]) -> Vec<Instruction> {
+        ops.iter()
+            .enumerate()
+            .map(|(index, &value)| Spanned {
+                value,
+                span: index..index + 1,
+            })
+            .collect()
+    }
+
+    const MUL: Op = Op::Mul { x: 2, y: 3 };
+
+    #[test]
+    fn arithmetic() {
+        let ops = program(&[
+            Op::Add { x: 1, y: 2 },
+            MUL,
+            Op::Sub { x: 4, y: 10 },
+            Op::Reset,
+            Op::Add { x: 5, y: 5 },
+            Op::Sub { x: 1, y: 4 },
+        ]);
+
+        assert_eq!(Machine::new(Toggles::Flat).run(&ops), 7);
+        assert_eq!(Machine::new(Toggles::Flat).run(&ops[..3]), 3);
+    }
+
+    #[test]
+    fn arithmetic_wraps_around() {
+        let big = Op::Mul {
+            x: i64::MAX,
+            y: i64::MAX,
+        };
+        assert_eq!(Machine::new(Toggles::Flat).run(&program(&[big])), 1);
+
+        let ops = program(
+            &[Op::Mul {
+                x: 999_999_999,
+                y: 999_999_999,
+            }; 10],
+        );
+        assert_eq!(
+            Machine::new(Toggles::Flat).run(&ops),
+            (999_999_999_i64 * 999_999_999).wrapping_mul(10)
+        );
+
+        let ops = program(&[Op::Add { x: i64::MAX, y: 1 }, Op::Sub { x: i64::MIN, y: 1 }]);
+        assert_eq!(Machine::new(Toggles::Flat).run(&ops), -1);
+    }
+
+    #[test]
+    fn toggles() {
+        let ops = program(&[Op::Dont, Op::Dont, MUL, Op::Do, MUL, Op::Do, MUL]);
+
+        assert_eq!(Machine::new(Toggles::Ignored).run(&ops), 18);
+        // The first do() re-enables everything
+        assert_eq!(Machine::new(Toggles::Flat).run(&ops), 12);
+        // Two don't() scopes need two do() to close
+        assert_eq!(Machine::new(Toggles::Nested).run(&ops), 6);
+        // Unbalanced do() does not enable ahead of time
+        let ops = program(&[Op::Do, Op::Do, Op::Dont, MUL]);
+        assert_eq!(Machine::new(Toggles::Nested).run(&ops), 0);
+    }
+
+    #[test]
+    fn disabled_reset_is_skipped() {
+        let ops = program(&[MUL, Op::Dont, Op::Reset, Op::Do, MUL]);
+
+        assert_eq!(Machine::new(Toggles::Flat).run(&ops), 12);
+    }
+
+    #[test]
+    fn trace_records_every_step() {
+        let ops = program(&[MUL, Op::Dont, MUL, Op::Do, Op::Reset]);
+
+        let mut machine = Machine::new(Toggles::Flat).with_trace();
+        machine.run(&ops);
+
+        let steps: Vec<(usize, bool, i64)> = machine
+            .trace()
+            .iter()
+            .map(|entry| (entry.counter, entry.executed, entry.accumulator))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                (1, true, 6),
+                (2, true, 6),
+                (3, false, 6),
+                (4, true, 6),
+                (5, true, 0)
+            ]
+        );
+        assert_eq!(
+            machine.trace()[2].to_string(),
+            format!(
+                "#3           2..3       {:<24} skipped  acc=6",
+                "Mul { x: 2, y: 3 }"
+            )
+        );
+
+        assert!(Machine::new(Toggles::Flat).trace().is_empty());
+    }
+}
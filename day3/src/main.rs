@@ -1,63 +1,28 @@
+mod grammar;
+mod highlight;
+mod machine;
 use std::{
     fs::File,
-    io::{BufReader, Read},
+    io::{self, Read},
 };
 
-#[derive(Debug)]
+use grammar::Grammar;
+use machine::{Machine, Toggles};
+use matcher::{Restart, Spanned};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Op {
     Mul { x: i64, y: i64 },
+    Add { x: i64, y: i64 },
+    Sub { x: i64, y: i64 },
+    Reset,
     Do,
     Dont,
 }
 
-#[derive(Clone, Copy)]
-enum ParserPattern {
-    String(&'static str),
-    Number(u64),
-}
-
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum PatternMatch {
-    Continue,
-    Abort,
-    DoneEarly,
-    Done,
-}
-
-impl ParserPattern {
-    fn match_pattern(&self, str: &str) -> PatternMatch {
-        match self {
-            ParserPattern::String(expected_str) => {
-                if *expected_str == str {
-                    PatternMatch::Done
-                } else if expected_str.starts_with(str) {
-                    PatternMatch::Continue
-                } else {
-                    PatternMatch::Abort
-                }
-            }
-            ParserPattern::Number(max_length) => {
-                let all_digits = str.chars().all(|e| e.is_digit(10));
-
-                if !all_digits {
-                    if str.len() > 1 {
-                        PatternMatch::DoneEarly
-                    } else {
-                        PatternMatch::Abort
-                    }
-                } else if str.len() as u64 == *max_length {
-                    PatternMatch::Done
-                } else if (str.len() as u64) < *max_length {
-                    PatternMatch::Continue
-                } else {
-                    PatternMatch::Abort
-                }
-            }
-        }
-    }
-}
+type Instruction = Spanned<Op>;
 
-fn load_input(path: &'static str) -> String {
+fn load_input(path: &'static str) -> File {
     let file = match File::open(path) {
         Ok(x) => x,
         Err(err) => {
@@ -68,170 +33,326 @@ fn load_input(path: &'static str) -> String {
         }
     };
 
-    let mut file_reader = BufReader::new(file);
+    file
+}
 
-    let mut out = String::new();
+// Corrupted memory is not necessarily valid UTF-8, so it is scanned as raw bytes,
+// chunk by chunk, without ever holding the whole input in memory
+fn parse_input(reader: impl Read, grammar: &Grammar) -> Vec<Instruction> {
+    grammar
+        .instructions()
+        .stream(reader)
+        .collect::<io::Result<Vec<Instruction>>>()
+        .unwrap()
+}
 
-    //Hmmm?
-    let _ = file_reader.read_to_string(&mut out).unwrap();
+fn part1(ops: &[Instruction]) -> i64 {
+    Machine::new(Toggles::Ignored).run(ops)
+}
 
-    out
+fn part2(ops: &[Instruction]) -> i64 {
+    Machine::new(Toggles::Flat).run(ops)
 }
 
-fn parse_input(data: &str) -> Vec<Op> {
-    const PATTERN_MUL: [ParserPattern; 5] = [
-        ParserPattern::String("mul("),
-        ParserPattern::Number(3),
-        ParserPattern::String(","),
-        ParserPattern::Number(3),
-        ParserPattern::String(")"),
-    ];
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
 
-    const PATTERN_DO: [ParserPattern; 1] = [ParserPattern::String("do()")];
-    const PATTERN_DONT: [ParserPattern; 1] = [ParserPattern::String("don't()")];
+    // Passing "-" reads the memory dump from stdin instead
+    let input: Box<dyn Read> = if args.iter().any(|arg| arg == "-") {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(load_input("./day3/input.txt"))
+    };
 
-    const PATTERNS: [&[ParserPattern]; 3] = [&PATTERN_MUL, &PATTERN_DO, &PATTERN_DONT];
+    let extended = args.iter().any(|arg| arg == "--extended");
+    let toggles = if args.iter().any(|arg| arg == "--nested") {
+        Toggles::Nested
+    } else {
+        Toggles::Flat
+    };
 
-    let data_chars: Vec<char> = data.chars().collect();
+    let mut grammar = Grammar::strict().with_extended(extended);
+    if args.iter().any(|arg| arg == "--every-byte") {
+        grammar = grammar.with_restart(Restart::EveryByte);
+    }
 
-    let mut out: Vec<Op> = Vec::new();
+    let highlight = args.iter().any(|arg| arg == "--highlight");
+    let lenient = args.iter().any(|arg| arg == "--lenient");
 
-    let mut current_value = String::new();
-    let mut temp = String::new();
+    let mut lenient_ops: Option<Vec<Instruction>> = None;
 
-    let mut current_state = 0;
-    let mut current_index = 0;
+    let ops = if highlight || lenient {
+        // These views need the memory itself, not just the instructions
+        let mut data: Vec<u8> = Vec::new();
+        let mut input = input;
+        input.read_to_end(&mut data).unwrap();
 
-    while current_index < data.len() {
-        temp.push(data_chars[current_index]);
+        let ops = parse_input(&data[..], &grammar);
 
-        let matches: Vec<PatternMatch> = PATTERNS
-            .iter()
-            .map(|e| {
-                if e.len() > current_state {
-                    e[current_state].match_pattern(&temp)
-                } else {
-                    PatternMatch::Abort
-                }
-            })
-            .collect();
+        if highlight {
+            highlight::highlight(&mut io::stdout().lock(), &data, &ops, Machine::new(toggles))
+                .unwrap();
+        }
 
-        if matches.iter().any(|&e| e == PatternMatch::Continue) {
-            current_index += 1;
-        } else if matches.iter().any(|&e| e == PatternMatch::Done) {
-            current_state += 1;
+        if lenient {
+            let mut lenient_grammar = Grammar::lenient().with_extended(extended);
+            if let Some(max_digits) = args
+                .iter()
+                .position(|arg| arg == "--max-digits")
+                .and_then(|index| args.get(index + 1))
+            {
+                lenient_grammar = lenient_grammar.with_max_digits(max_digits.parse().unwrap());
+            }
 
-            current_value += &temp;
-            temp.clear();
+            let found = parse_input(&data[..], &lenient_grammar);
 
-            current_index += 1;
-        } else if matches.iter().any(|&e| e == PatternMatch::DoneEarly) {
-            temp.pop();
+            for extra in grammar::extra_matches(&ops, &found) {
+                println!(
+                    "Extra {:>7}..{:<7} {:<24} {}",
+                    extra.span.start,
+                    extra.span.end,
+                    format!("{:?}", extra.value),
+                    String::from_utf8_lossy(&data[extra.span.clone()])
+                );
+            }
 
-            current_state += 1;
+            lenient_ops = Some(found);
+        }
 
-            current_value += &temp;
-            temp.clear();
-        } else {
-            current_state = 0;
+        ops
+    } else {
+        parse_input(input, &grammar)
+    };
 
-            temp.clear();
-            current_value.clear();
+    if args.iter().any(|arg| arg == "--trace") {
+        let mut machine = Machine::new(toggles).with_trace();
+        machine.run(&ops);
 
-            current_index += 1;
+        for entry in machine.trace() {
+            println!("{}", entry);
         }
+    }
 
-        if !matches.iter().any(|&e| e == PatternMatch::Continue)
-            && matches
-                .iter()
-                .enumerate()
-                .filter(|&(index, &e)| {
-                    e == PatternMatch::Done && PATTERNS[index].len() == current_state
-                })
-                .count()
-                == 1
-        {
-            let index = matches
-                .iter()
-                .enumerate()
-                .filter_map(|(index, &v)| {
-                    if v == PatternMatch::Done {
-                        Some(index)
-                    } else {
-                        None
-                    }
-                })
-                .next()
-                .unwrap();
+    println!("Part 1: {}", part1(&ops));
+    println!("Part 2: {}", part2(&ops));
 
-            match index {
-                0 => {
-                    current_value = current_value.replace("mul(", "");
-                    current_value = current_value.replace(")", "");
+    if toggles == Toggles::Nested {
+        println!("Part 2 (nested): {}", Machine::new(toggles).run(&ops));
+    }
+
+    if let Some(lenient_ops) = lenient_ops {
+        println!("Lenient part 1: {}", part1(&lenient_ops));
+        println!("Lenient part 2: {}", part2(&lenient_ops));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use super::*;
 
-                    let mut it = current_value
-                        .split(",")
-                        .map(|e| i64::from_str_radix(e, 10).unwrap());
+    // Small xorshift generator, so every failing case can be reproduced from its seed
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+    }
 
-                    out.push(Op::Mul {
-                        x: it.next().unwrap(),
-                        y: it.next().unwrap(),
+    // Noise that must never produce an instruction on its own. As in the original
+    // parser, a partial instruction swallows the byte it fails on, so the generator
+    // always follows noise with a byte of garbage
+    const NOISE: [&str; 16] = [
+        "mul(12",
+        "mul(1234,5)",
+        "mul(5,1234)",
+        "don't(",
+        "do(",
+        "don't",
+        "do_not_mul",
+        "mul[3,7]",
+        "mul(3,7]",
+        "mul ( 2, 3)",
+        "mul(2 ,3)",
+        "MUL(2,3)",
+        "mul(-2,3)",
+        "mul(,3)",
+        "mul(3,)",
+        "mumul(",
+    ];
+
+    // Filler bytes, including invalid UTF-8 and NUL, none of which can continue a
+    // partial instruction
+    const GARBAGE: &[u8] = b"xyz!@#$%^&*[]{}<>?;: \n\t\0\xff\xfe";
+
+    // Appends `text` and returns where it landed
+    fn emit(data: &mut Vec<u8>, text: &str) -> Range<usize> {
+        let start = data.len();
+        data.extend(text.bytes());
+        start..data.len()
+    }
+
+    fn generate(seed: u64) -> (Vec<u8>, Vec<Instruction>) {
+        let mut rng = Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1);
+
+        let mut data: Vec<u8> = Vec::new();
+        let mut expected: Vec<Instruction> = Vec::new();
+
+        let mul = |rng: &mut Rng| {
+            let (x, y) = (rng.below(1000) as i64, rng.below(1000) as i64);
+            (format!("mul({},{})", x, y), Op::Mul { x, y })
+        };
+
+        for _ in 0..rng.below(40) {
+            match rng.below(8) {
+                0 | 1 => {
+                    let (text, op) = mul(&mut rng);
+                    let span = emit(&mut data, &text);
+                    expected.push(Spanned { value: op, span });
+                }
+                2 => {
+                    let span = emit(&mut data, "do()");
+                    expected.push(Spanned {
+                        value: Op::Do,
+                        span,
                     });
                 }
-                1 => out.push(Op::Do),
-                2 => out.push(Op::Dont),
+                3 => {
+                    let span = emit(&mut data, "don't()");
+                    expected.push(Spanned {
+                        value: Op::Dont,
+                        span,
+                    });
+                }
+                4 => {
+                    // do() immediately followed by mul
+                    let span = emit(&mut data, "do()");
+                    expected.push(Spanned {
+                        value: Op::Do,
+                        span,
+                    });
+
+                    let (text, op) = mul(&mut rng);
+                    let span = emit(&mut data, &text);
+                    expected.push(Spanned { value: op, span });
+                }
+                5 => {
+                    // The outer mul swallows the start of the inner one, so neither
+                    // is an instruction
+                    let (text, _) = mul(&mut rng);
+                    emit(&mut data, "mul(");
+                    emit(&mut data, &text);
+                    emit(&mut data, ",4)");
+                }
+                6 => {
+                    emit(&mut data, NOISE[rng.below(NOISE.len() as u64) as usize]);
+                    data.push(GARBAGE[rng.below(GARBAGE.len() as u64) as usize]);
+                }
                 _ => {
-                    unreachable!()
+                    for _ in 0..rng.below(6) {
+                        data.push(GARBAGE[rng.below(GARBAGE.len() as u64) as usize]);
+                    }
                 }
             }
+        }
+
+        (data, expected)
+    }
+
+    fn parse(data: &[u8]) -> Vec<Instruction> {
+        parse_input(data, &Grammar::strict())
+    }
+
+    #[test]
+    fn round_trip_recovers_inserted_instructions() {
+        for seed in 0..500 {
+            let (data, expected) = generate(seed);
+
+            assert_eq!(
+                parse(&data),
+                expected,
+                "seed {}: {:?}",
+                seed,
+                String::from_utf8_lossy(&data)
+            );
+        }
+    }
+
+    #[test]
+    fn round_trip_across_chunk_boundaries() {
+        let matcher = Grammar::strict().instructions();
+
+        for seed in 0..100 {
+            let (data, expected) = generate(seed);
+
+            for chunk_size in 1..=8 {
+                let found: Vec<Instruction> = matcher
+                    .stream(&data[..])
+                    .with_chunk_size(chunk_size)
+                    .map(Result::unwrap)
+                    .collect();
 
-            current_value.clear();
-            current_state = 0;
+                assert_eq!(found, expected, "seed {}, chunk size {}", seed, chunk_size);
+            }
         }
     }
 
-    out
-}
+    #[test]
+    fn nested_mul_is_swallowed() {
+        assert!(parse(b"mul(mul(2,3),4)").is_empty());
+    }
 
-fn part1(ops: &[Op]) -> i64 {
-    ops.iter()
-        .filter_map(|e| match e {
-            Op::Mul { x, y } => Some(x * y),
-            _ => None,
-        })
-        .sum()
-}
+    #[test]
+    fn partial_instruction_swallows_the_byte_it_fails_on() {
+        let ops = |data: &[u8]| -> Vec<Op> { parse(data).into_iter().map(|e| e.value).collect() };
 
-fn part2(ops: &[Op]) -> i64 {
-    let mut enabled = true;
-    ops.iter()
-        .filter_map(|e| {
-            if enabled {
-                match e {
-                    Op::Mul { x, y } => Some(x * y),
-                    Op::Dont => {
-                        enabled = false;
-                        None
-                    }
-                    _ => None,
-                }
-            } else {
-                match e {
-                    Op::Do => {
-                        enabled = true;
-                        None
-                    }
-                    _ => None,
-                }
+        assert!(ops(b"mul(12mul(3,4)mumul(5,6)don't(do()").is_empty());
+        assert_eq!(
+            ops(b"mul(12xmul(3,4)mumxul(5,6)don't(xdo()"),
+            vec![Op::Mul { x: 3, y: 4 }, Op::Do]
+        );
+    }
+
+    // The original parser needed DoneEarly and temp.pop() to end an operand shorter
+    // than three digits on the byte after it, so every operand length is checked
+    // against both terminators
+    #[test]
+    fn short_operands_end_on_the_next_byte() {
+        for x in ["7", "42", "999"] {
+            for y in ["0", "10", "123"] {
+                let text = format!("mul({},{})", x, y);
+                let expected = Op::Mul {
+                    x: x.parse().unwrap(),
+                    y: y.parse().unwrap(),
+                };
+
+                let ops = parse(text.as_bytes());
+                assert_eq!(
+                    ops,
+                    vec![Spanned {
+                        value: expected,
+                        span: 0..text.len()
+                    }],
+                    "{}",
+                    text
+                );
             }
-        })
-        .sum()
-}
+        }
 
-fn main() {
-    let code = load_input("./day3/input.txt");
-    let ops = parse_input(&code);
+        assert!(parse(b"mul(12,3mul(4,)").is_empty());
+    }
 
-    println!("Part 1: {}", part1(&ops));
-    println!("Part 2: {}", part2(&ops));
+    #[test]
+    fn overlong_operands_are_rejected() {
+        assert!(parse(b"mul(1234,5)mul(5,1234)").is_empty());
+    }
 }
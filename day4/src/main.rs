@@ -1,10 +1,14 @@
-mod structs;
+mod search;
+mod template;
 use std::{
+    collections::HashSet,
     fs::File,
-    io::{BufRead, BufReader, Read},
+    io::{self, BufReader, Read},
 };
 
-use structs::Matrix;
+use grid::{ImageFormat, Matrix, Rgb};
+use search::WordSearch;
+use template::Template;
 
 fn load_input(path: &'static str) -> Matrix<char> {
     let file = match File::open(path) {
@@ -17,99 +21,134 @@ fn load_input(path: &'static str) -> Matrix<char> {
         }
     };
 
-    let file_reader = BufReader::new(file);
+    let mut file_reader = BufReader::new(file);
 
-    let data: Vec<String> = file_reader
-        .lines()
-        .filter_map(|line_res| line_res.ok())
-        .collect();
+    let mut text = String::new();
+    file_reader.read_to_string(&mut text).unwrap();
 
-    let columns = data[0].len();
-    let rows = data.len();
-
-    Matrix::new(rows, columns).with_data(data.join("").chars().collect())
+    Matrix::parse(&text, |c| c).unwrap_or_else(|err| panic!("Invalid input: {}", err))
 }
 
 fn part1(input: &Matrix<char>) -> i64 {
-    let mut count = 0;
+    WordSearch::new(&["XMAS"]).find(input).len() as i64
+}
 
-    let mut current_index = 0;
-    while let Some(row) = input.row(current_index) {
-        let row = row.iter().collect::<String>();
-        count += row.matches("XMAS").count() as i64;
-        count += row.matches("SAMX").count() as i64;
+fn part2(input: &Matrix<char>) -> i64 {
+    Template::parse("M.S\n.A.\nM.S")
+        .unwrap()
+        .with_rotations()
+        .find(input)
+        .len() as i64
+}
 
-        current_index += 1;
-    }
+const HIT: Rgb = [255, 200, 0];
 
-    let mut current_index = 0;
-    while let Some(column) = input.column(current_index) {
-        let column = column.iter().collect::<String>();
-        count += column.matches("XMAS").count() as i64;
-        count += column.matches("SAMX").count() as i64;
+// Every cell covered by an occurrence of one of `words`
+fn hit_cells(input: &Matrix<char>, words: &[&str]) -> HashSet<(usize, usize)> {
+    let mut cells = HashSet::new();
 
-        current_index += 1;
+    for hit in WordSearch::new(words).find(input) {
+        let (row_step, column_step) = hit.direction.offset();
+
+        for i in 0..words[hit.word].chars().count() as isize {
+            cells.insert((
+                (hit.start.0 as isize + i * row_step) as usize,
+                (hit.start.1 as isize + i * column_step) as usize,
+            ));
+        }
     }
 
-    let mut current_index = 0;
-    while let Some(left_diagonal) = input.left_diagonal(current_index) {
-        let left_diagonal = left_diagonal.iter().collect::<String>();
-        count += left_diagonal.matches("XMAS").count() as i64;
-        count += left_diagonal.matches("SAMX").count() as i64;
+    cells
+}
 
-        current_index += 1;
-    }
+fn main() {
+    let input = load_input("./day4/input.txt");
 
-    let mut current_index = 0;
-    while let Some(right_diagonal) = input.right_diagonal(current_index) {
-        let right_diagonal = right_diagonal.iter().collect::<String>();
-        count += right_diagonal.matches("XMAS").count() as i64;
-        count += right_diagonal.matches("SAMX").count() as i64;
+    println!("Part 1: {}", part1(&input));
+    println!("Part 2: {}", part2(&input));
 
-        current_index += 1;
-    }
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let value = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|index| args.get(index + 1))
+    };
 
-    count
-}
+    let words: Vec<&str> = match value("--words") {
+        Some(words) => {
+            let words: Vec<&str> = words.split(',').collect();
 
-fn part2(input: &Matrix<char>) -> i64 {
-    let mut count = 0;
-
-    for i in 0..input.data().len() {
-        if input.data()[i] == 'A'
-            && i % input.column_count() != 0
-            && i % input.column_count() != input.column_count() - 1
-            && i / input.row_count() != 0
-            && i / input.row_count() != input.row_count() - 1
-        {
-            let left_diagonal_start = i - input.column_count() - 1;
-            let right_diagonal_start: usize = i - input.column_count() + 1;
-
-            let right_diagonal = input
-                .right_diagonal_at_index(right_diagonal_start)
-                .unwrap()
-                .iter()
-                .collect::<String>();
-            let left_diagonal = input
-                .left_diagonal_at_index(left_diagonal_start)
-                .unwrap()
-                .iter()
-                .collect::<String>();
-
-            if (right_diagonal.starts_with("MAS") || right_diagonal.starts_with("SAM"))
-                && (left_diagonal.starts_with("MAS") || left_diagonal.starts_with("SAM"))
-            {
-                count += 1
+            for hit in WordSearch::new(&words).find(&input) {
+                println!(
+                    "{} at ({}, {}) going {:?}",
+                    words[hit.word], hit.start.0, hit.start.1, hit.direction
+                );
             }
+
+            words
         }
-    }
+        None => vec!["XMAS"],
+    };
 
-    count
-}
+    // Both views mark the cells of every hit of the searched words
+    let show = args.iter().any(|arg| arg == "--show");
+    let image = value("--image");
+    if show || image.is_some() {
+        let cells = hit_cells(&input, &words);
+
+        if show {
+            input
+                .render_ansi(
+                    &mut io::stdout().lock(),
+                    |&c| c,
+                    |position, _| cells.contains(&position).then_some(HIT),
+                )
+                .unwrap();
+        }
 
-fn main() {
-    let input = load_input("./day4/input.txt");
+        if let Some(path) = image {
+            let scale = value("--scale").map_or(4, |scale| scale.parse().unwrap());
+            let mut file = File::create(path).unwrap();
+
+            input
+                .render_image(
+                    &mut file,
+                    ImageFormat::from_path(path),
+                    scale,
+                    |position, _| {
+                        if cells.contains(&position) {
+                            HIT
+                        } else {
+                            [40, 40, 40]
+                        }
+                    },
+                )
+                .unwrap();
+        }
+    }
 
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
+    // Rows of the template are separated by '/', e.g. --template .M./MAS/.S.
+    if let Some(text) = value("--template") {
+        let mut template = Template::parse(&text.replace('/', "\n"))
+            .unwrap_or_else(|err| panic!("Invalid template: {}", err));
+        if args.iter().any(|arg| arg == "--rotations") {
+            template = template.with_rotations();
+        }
+        if args.iter().any(|arg| arg == "--reflections") {
+            template = template.with_reflections();
+        }
+
+        let matches = template.find(&input);
+        for found in &matches {
+            println!(
+                "Match at ({}, {}) in variant {}",
+                found.position.0, found.position.1, found.variant
+            );
+        }
+        println!(
+            "{} matches over {} variants",
+            matches.len(),
+            template.variants().len()
+        );
+    }
 }
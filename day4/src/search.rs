@@ -0,0 +1,320 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use grid::Matrix;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction {
+    // (row, column) step
+    pub fn offset(&self) -> (isize, isize) {
+        match self {
+            Direction::North => (-1, 0),
+            Direction::NorthEast => (-1, 1),
+            Direction::East => (0, 1),
+            Direction::SouthEast => (1, 1),
+            Direction::South => (1, 0),
+            Direction::SouthWest => (1, -1),
+            Direction::West => (0, -1),
+            Direction::NorthWest => (-1, -1),
+        }
+    }
+
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::NorthEast => Direction::SouthWest,
+            Direction::East => Direction::West,
+            Direction::SouthEast => Direction::NorthWest,
+            Direction::South => Direction::North,
+            Direction::SouthWest => Direction::NorthEast,
+            Direction::West => Direction::East,
+            Direction::NorthWest => Direction::SouthEast,
+        }
+    }
+}
+
+type Cell = (usize, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Hit {
+    // Index into the word list the search was built from
+    pub word: usize,
+    pub start: Cell,
+    pub direction: Direction,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Output {
+    word: usize,
+    length: usize,
+    reversed: bool,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    next: HashMap<char, usize>,
+    fail: usize,
+    outputs: Vec<Output>,
+}
+
+// Aho-Corasick automaton over every word and its reverse. Each line of the grid only
+// has to be read in one direction: a reversed word found going one way is the word
+// itself going the other way.
+#[derive(Debug)]
+pub struct WordSearch {
+    nodes: Vec<Node>,
+}
+
+impl WordSearch {
+    pub fn new(words: &[&str]) -> Self {
+        let mut nodes: Vec<Node> = vec![Node::default()];
+
+        for (index, word) in words.iter().enumerate() {
+            let chars: Vec<char> = word.chars().collect();
+            let reversed: Vec<char> = chars.iter().rev().copied().collect();
+
+            Self::insert(&mut nodes, &chars, index, false);
+
+            // A palindrome reads the same both ways and must only be found once
+            if reversed != chars {
+                Self::insert(&mut nodes, &reversed, index, true);
+            }
+        }
+
+        Self::link(&mut nodes);
+
+        Self { nodes }
+    }
+
+    fn insert(nodes: &mut Vec<Node>, pattern: &[char], word: usize, reversed: bool) {
+        if pattern.is_empty() {
+            return;
+        }
+
+        let mut state = 0;
+        for &c in pattern {
+            state = match nodes[state].next.get(&c) {
+                Some(&next) => next,
+                None => {
+                    nodes.push(Node::default());
+                    let next = nodes.len() - 1;
+                    nodes[state].next.insert(c, next);
+                    next
+                }
+            };
+        }
+
+        nodes[state].outputs.push(Output {
+            word,
+            length: pattern.len(),
+            reversed,
+        });
+    }
+
+    fn link(nodes: &mut [Node]) {
+        let mut queue: VecDeque<usize> = nodes[0].next.values().copied().collect();
+
+        while let Some(state) = queue.pop_front() {
+            let edges: Vec<(char, usize)> =
+                nodes[state].next.iter().map(|(&c, &n)| (c, n)).collect();
+
+            for (c, next) in edges {
+                let mut fail = nodes[state].fail;
+                while fail != 0 && !nodes[fail].next.contains_key(&c) {
+                    fail = nodes[fail].fail;
+                }
+
+                let fail = match nodes[fail].next.get(&c) {
+                    Some(&target) if target != next => target,
+                    _ => 0,
+                };
+
+                nodes[next].fail = fail;
+
+                let inherited = nodes[fail].outputs.clone();
+                nodes[next].outputs.extend(inherited);
+
+                queue.push_back(next);
+            }
+        }
+    }
+
+    fn step(&self, mut state: usize, c: char) -> usize {
+        loop {
+            if let Some(&next) = self.nodes[state].next.get(&c) {
+                return next;
+            }
+            if state == 0 {
+                return 0;
+            }
+            state = self.nodes[state].fail;
+        }
+    }
+
+    // Every occurrence of every word, in any of the eight directions
+    pub fn find(&self, grid: &Matrix<char>) -> Vec<Hit> {
+        let mut seen: HashSet<(usize, Cell, Cell)> = HashSet::new();
+        let mut out: Vec<Hit> = Vec::new();
+
+        let forward = [
+            Direction::East,
+            Direction::South,
+            Direction::SouthEast,
+            Direction::SouthWest,
+        ];
+
+        for direction in forward {
+            let (row_step, column_step) = direction.offset();
+
+            // A line starts on every cell whose predecessor lies outside of the grid
+            let starts = grid
+                .cells()
+                .map(|(position, _)| position)
+                .filter(|&(row, column)| {
+                    let previous_row = row.checked_add_signed(-row_step);
+                    let previous_column = column.checked_add_signed(-column_step);
+
+                    match (previous_row, previous_column) {
+                        (Some(row), Some(column)) => grid.get(row, column).is_none(),
+                        _ => true,
+                    }
+                });
+
+            for (mut row, mut column) in starts {
+                let mut state = 0;
+
+                while let Some(&c) = grid.get(row, column) {
+                    state = self.step(state, c);
+
+                    for output in &self.nodes[state].outputs {
+                        let back = output.length as isize - 1;
+                        let first = (
+                            (row as isize - back * row_step) as usize,
+                            (column as isize - back * column_step) as usize,
+                        );
+                        let last = (row, column);
+
+                        let hit = if output.reversed {
+                            Hit {
+                                word: output.word,
+                                start: last,
+                                direction: direction.opposite(),
+                            }
+                        } else {
+                            Hit {
+                                word: output.word,
+                                start: first,
+                                direction,
+                            }
+                        };
+
+                        // Single letters sit on four lines at once but are one hit
+                        if seen.insert((output.word, first.min(last), first.max(last))) {
+                            out.push(hit);
+                        }
+                    }
+
+                    match (
+                        row.checked_add_signed(row_step),
+                        column.checked_add_signed(column_step),
+                    ) {
+                        (Some(next_row), Some(next_column)) => {
+                            row = next_row;
+                            column = next_column;
+                        }
+                        _ => break,
+                    }
+                }
+            }
+        }
+
+        out.sort();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(words: &[&str], text: &str) -> Vec<Hit> {
+        let grid = Matrix::parse(text, |c| c).unwrap();
+        WordSearch::new(words).find(&grid)
+    }
+
+    fn hit(word: usize, start: Cell, direction: Direction) -> Hit {
+        Hit {
+            word,
+            start,
+            direction,
+        }
+    }
+
+    #[test]
+    fn palindromes_are_found_once() {
+        assert_eq!(find(&["ABA"], "ABA"), vec![hit(0, (0, 0), Direction::East)]);
+        assert_eq!(
+            find(&["ABA"], "A\nB\nA"),
+            vec![hit(0, (0, 0), Direction::South)]
+        );
+
+        // Single letters lie on four lines through every cell
+        assert_eq!(
+            find(&["A"], "XXX\nXAX\nXXX"),
+            vec![hit(0, (1, 1), Direction::East)]
+        );
+    }
+
+    #[test]
+    fn overlapping_words() {
+        assert_eq!(
+            find(&["XMAS", "MAS", "AS"], "XMAS"),
+            vec![
+                hit(0, (0, 0), Direction::East),
+                hit(1, (0, 1), Direction::East),
+                hit(2, (0, 2), Direction::East),
+            ]
+        );
+
+        // One word sharing its letters with its own reverse
+        assert_eq!(
+            find(&["XMAS"], "XMASAMX"),
+            vec![
+                hit(0, (0, 0), Direction::East),
+                hit(0, (0, 6), Direction::West),
+            ]
+        );
+    }
+
+    #[test]
+    fn hits_start_on_the_first_letter() {
+        let text = "S..S\n.AA.\n.MM.\nX..X";
+
+        assert_eq!(
+            find(&["XMAS"], text),
+            vec![
+                hit(0, (3, 0), Direction::NorthEast),
+                hit(0, (3, 3), Direction::NorthWest),
+            ]
+        );
+
+        let hits = find(&["XMAS"], "SAMX\nXMAS");
+        assert_eq!(
+            hits,
+            vec![
+                hit(0, (0, 3), Direction::West),
+                hit(0, (1, 0), Direction::East),
+            ]
+        );
+    }
+}
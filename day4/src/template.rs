@@ -0,0 +1,134 @@
+use grid::{Matrix, MatrixError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Match {
+    // Top left corner of the template in the grid
+    pub position: (usize, usize),
+    // Which orientation of the template matched
+    pub variant: usize,
+}
+
+// A small grid of letters where `.` matches anything, optionally expanded to all of
+// its distinct rotations and reflections
+#[derive(Debug, Clone)]
+pub struct Template {
+    variants: Vec<Matrix<Option<char>>>,
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Self, MatrixError> {
+        let cells = Matrix::parse(text, |c| if c == '.' { None } else { Some(c) })?;
+
+        Ok(Self {
+            variants: vec![cells],
+        })
+    }
+
+    pub fn with_rotations(self) -> Self {
+        self.expand(|cells| vec![cells.rotate_90(), cells.rotate_180(), cells.rotate_270()])
+    }
+
+    pub fn with_reflections(self) -> Self {
+        self.expand(|cells| vec![cells.flip_horizontal()])
+    }
+
+    pub fn variants(&self) -> &[Matrix<Option<char>>] {
+        &self.variants
+    }
+
+    fn expand(mut self, f: impl Fn(&Matrix<Option<char>>) -> Vec<Matrix<Option<char>>>) -> Self {
+        let expanded: Vec<Matrix<Option<char>>> = self.variants.iter().flat_map(f).collect();
+
+        for variant in expanded {
+            if !self.variants.contains(&variant) {
+                self.variants.push(variant);
+            }
+        }
+
+        self
+    }
+
+    pub fn find(&self, grid: &Matrix<char>) -> Vec<Match> {
+        let mut out: Vec<Match> = Vec::new();
+
+        for (variant, cells) in self.variants.iter().enumerate() {
+            let windows = grid.rectangular_windows(cells.row_count(), cells.column_count());
+
+            for window in windows {
+                let matches = cells.cells().all(|(position, expected)| match expected {
+                    Some(expected) => window[position] == *expected,
+                    None => true,
+                });
+
+                if matches {
+                    out.push(Match {
+                        position: window.origin(),
+                        variant,
+                    });
+                }
+            }
+        }
+
+        out.sort();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(text: &str) -> Matrix<char> {
+        Matrix::parse(text, |c| c).unwrap()
+    }
+
+    fn positions(matches: &[Match]) -> Vec<(usize, usize)> {
+        matches.iter().map(|m| m.position).collect()
+    }
+
+    #[test]
+    fn wildcards_match_anything() {
+        let template = Template::parse("A.B").unwrap();
+
+        assert_eq!(
+            positions(&template.find(&grid("AXB\nAYC\nAAB"))),
+            vec![(0, 0), (2, 0)]
+        );
+        assert!(template.find(&grid("AB")).is_empty());
+    }
+
+    #[test]
+    fn variants_are_distinct() {
+        let count = |text: &str, rotations: bool, reflections: bool| {
+            let mut template = Template::parse(text).unwrap();
+            if rotations {
+                template = template.with_rotations();
+            }
+            if reflections {
+                template = template.with_reflections();
+            }
+            template.variants().len()
+        };
+
+        assert_eq!(count("A", true, true), 1);
+        assert_eq!(count("AB", false, true), 2);
+        assert_eq!(count("AB", true, true), 4);
+        assert_eq!(count("M.S\n.A.\nM.S", true, false), 4);
+        assert_eq!(count("M.S\n.A.\nM.S", true, true), 4);
+        assert_eq!(count("AB\nC.", true, true), 8);
+    }
+
+    #[test]
+    fn plus_shaped_mas() {
+        let plus = Template::parse(".M.\nMAS\n.S.").unwrap();
+        let text = grid(".M..S.\nMASMAS\n.S..M.");
+
+        assert_eq!(positions(&plus.find(&text)), vec![(0, 0)]);
+
+        let rotated = plus.with_rotations();
+        let matches = rotated.find(&text);
+        assert_eq!(positions(&matches), vec![(0, 0), (0, 3)]);
+        assert_eq!(matches[0].variant, 0);
+        assert_ne!(matches[1].variant, 0);
+    }
+}
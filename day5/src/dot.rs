@@ -0,0 +1,201 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+use crate::explain::Explanation;
+
+// Rules as a GraphViz digraph, restricted to the pages of one update when an
+// explanation of it is given. Rules on a cycle are red, rules between pages next
+// to each other in the fixed order are green since they decide it, rules the
+// update breaks are bold and otherwise blue, and pages the fix moves are filled
+pub fn to_dot(rules: &HashMap<i64, Vec<i64>>, explanation: Option<&Explanation>) -> String {
+    let pages: Option<HashSet<i64>> = explanation.map(|e| e.update.iter().copied().collect());
+    let drawn = |page: &i64| pages.as_ref().is_none_or(|pages| pages.contains(page));
+
+    let mut edges: Vec<(i64, i64)> = rules
+        .iter()
+        .filter(|(before, _)| drawn(before))
+        .flat_map(|(before, afters)| {
+            afters
+                .iter()
+                .filter(|after| drawn(after))
+                .map(move |after| (*before, *after))
+        })
+        .collect();
+    edges.sort_unstable();
+    edges.dedup();
+
+    let mut nodes: Vec<i64> = match explanation {
+        Some(explanation) => explanation.update.clone(),
+        None => edges.iter().flat_map(|(a, b)| [*a, *b]).collect(),
+    };
+    nodes.sort_unstable();
+    nodes.dedup();
+
+    let components = components(&nodes, &edges);
+
+    let broken: HashSet<(i64, i64)> = explanation
+        .into_iter()
+        .flat_map(|e| e.violations.iter().map(|v| (v.before, v.after)))
+        .collect();
+    let fix = explanation.and_then(|e| e.fix.as_ref().ok());
+    let moved: HashSet<i64> = fix
+        .into_iter()
+        .flat_map(|fix| fix.moves.iter().map(|step| step.page))
+        .collect();
+    let deciding: HashSet<(i64, i64)> = fix
+        .into_iter()
+        .flat_map(|fix| fix.fixed.windows(2).map(|pair| (pair[0], pair[1])))
+        .collect();
+
+    let mut out = String::new();
+    writeln!(out, "digraph rules {{").unwrap();
+    writeln!(out, "    node [shape=circle];").unwrap();
+
+    for node in &nodes {
+        if moved.contains(node) {
+            writeln!(out, "    {} [style=filled, fillcolor=lightyellow];", node).unwrap();
+        } else {
+            writeln!(out, "    {};", node).unwrap();
+        }
+    }
+
+    for (before, after) in &edges {
+        let mut attributes: Vec<&str> = Vec::new();
+        if components[before] == components[after] {
+            attributes.push("color=red");
+        } else if deciding.contains(&(*before, *after)) {
+            attributes.push("color=darkgreen");
+        } else if broken.contains(&(*before, *after)) {
+            attributes.push("color=blue");
+        }
+        if broken.contains(&(*before, *after)) {
+            attributes.push("penwidth=2");
+        }
+
+        if attributes.is_empty() {
+            writeln!(out, "    {} -> {};", before, after).unwrap();
+        } else {
+            writeln!(
+                out,
+                "    {} -> {} [{}];",
+                before,
+                after,
+                attributes.join(", ")
+            )
+            .unwrap();
+        }
+    }
+
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+// Strongly connected component of every page, by Kosaraju's algorithm. A rule
+// lies on a cycle iff both its pages are in the same component
+fn components(nodes: &[i64], edges: &[(i64, i64)]) -> HashMap<i64, usize> {
+    let mut forward: HashMap<i64, Vec<i64>> = HashMap::new();
+    let mut backward: HashMap<i64, Vec<i64>> = HashMap::new();
+    for (before, after) in edges {
+        forward.entry(*before).or_default().push(*after);
+        backward.entry(*after).or_default().push(*before);
+    }
+
+    // Pages in order of finishing a depth-first search of the rules
+    let mut finished: Vec<i64> = Vec::with_capacity(nodes.len());
+    let mut visited: HashSet<i64> = HashSet::new();
+    for &root in nodes {
+        if !visited.insert(root) {
+            continue;
+        }
+
+        let mut stack: Vec<(i64, usize)> = vec![(root, 0)];
+        while let Some((page, next)) = stack.pop() {
+            match forward.get(&page).and_then(|afters| afters.get(next)) {
+                Some(&after) => {
+                    stack.push((page, next + 1));
+                    if visited.insert(after) {
+                        stack.push((after, 0));
+                    }
+                }
+                None => finished.push(page),
+            }
+        }
+    }
+
+    // Search the reversed rules in reverse finishing order, each search is one component
+    let mut components: HashMap<i64, usize> = HashMap::new();
+    for (component, &root) in finished.iter().rev().enumerate() {
+        if components.contains_key(&root) {
+            continue;
+        }
+
+        components.insert(root, component);
+        let mut stack = vec![root];
+        while let Some(page) = stack.pop() {
+            for before in backward.get(&page).into_iter().flatten() {
+                if !components.contains_key(before) {
+                    components.insert(*before, component);
+                    stack.push(*before);
+                }
+            }
+        }
+    }
+
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{explain, relation::Relation, rules_from_pairs};
+
+    #[test]
+    fn strongly_connected_components() {
+        let components = components(
+            &[1, 2, 3, 4, 5],
+            &[(1, 2), (2, 1), (2, 3), (3, 4), (4, 3), (4, 5)],
+        );
+
+        assert_eq!(components[&1], components[&2]);
+        assert_eq!(components[&3], components[&4]);
+        assert_ne!(components[&1], components[&3]);
+        assert_ne!(components[&3], components[&5]);
+        assert_ne!(components[&1], components[&5]);
+    }
+
+    #[test]
+    fn two_cycle_is_red() {
+        let dot = to_dot(&rules_from_pairs(&[(1, 2), (2, 1), (2, 3)]), None);
+
+        assert_eq!(
+            dot,
+            "digraph rules {\n    node [shape=circle];\n    1;\n    2;\n    3;\n    \
+             1 -> 2 [color=red];\n    2 -> 1 [color=red];\n    2 -> 3;\n}\n"
+        );
+    }
+
+    #[test]
+    fn fix_is_highlighted() {
+        let rules = rules_from_pairs(&[(97, 75), (75, 47), (97, 47), (47, 13), (61, 62)]);
+        let explanation = explain::explain(&Relation::new(&rules), 0, &[75, 97, 47]);
+
+        let dot = to_dot(&rules, Some(&explanation));
+
+        // Only the pages of the update are drawn
+        assert!(!dot.contains("13"));
+        assert!(!dot.contains("61"));
+        assert!(dot.contains("    75 [style=filled, fillcolor=lightyellow];\n"));
+        assert!(dot.contains("    97;\n"));
+        // Broken, and deciding the fixed order 97,75,47
+        assert!(dot.contains("    97 -> 75 [color=darkgreen, penwidth=2];\n"));
+        assert!(dot.contains("    75 -> 47 [color=darkgreen];\n"));
+        assert!(dot.contains("    97 -> 47;\n"));
+
+        // Broken without deciding anything
+        let explanation = explain::explain(&Relation::new(&rules), 1, &[47, 75, 97]);
+        let dot = to_dot(&rules, Some(&explanation));
+        assert!(dot.contains("    97 -> 47 [color=blue, penwidth=2];\n"));
+    }
+}
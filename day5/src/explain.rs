@@ -0,0 +1,461 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    order::{self, CycleError},
+    relation::Relation,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    // The broken rule is `before|after`, yet `after` comes first in the update
+    pub before: i64,
+    pub after: i64,
+    pub before_position: usize,
+    pub after_position: usize,
+}
+
+// Positions are those of the update as left by the moves before this one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Move {
+    pub page: i64,
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    pub moves: Vec<Move>,
+    pub fixed: Vec<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    pub index: usize,
+    pub update: Vec<i64>,
+    pub violations: Vec<Violation>,
+    pub fix: Result<Fix, CycleError>,
+}
+
+pub fn explain(relation: &Relation, index: usize, update: &[i64]) -> Explanation {
+    let mut violations = Vec::new();
+    for (after_position, after) in update.iter().enumerate() {
+        for (before_position, before) in update.iter().enumerate().skip(after_position + 1) {
+            if relation.precedes(*before, *after) {
+                violations.push(Violation {
+                    before: *before,
+                    after: *after,
+                    before_position,
+                    after_position,
+                });
+            }
+        }
+    }
+
+    Explanation {
+        index,
+        update: update.to_vec(),
+        violations,
+        fix: fix(relation, update),
+    }
+}
+
+// The fewest moves leave in place the largest set of pages that no chain of rules
+// orders differently from the update. Those pages stay in their relative order,
+// every other page is moved to where a topological sort puts it
+fn fix(relation: &Relation, update: &[i64]) -> Result<Fix, CycleError> {
+    let n = update.len();
+
+    let mut rules: HashMap<i64, Vec<i64>> = HashMap::new();
+    for before in update {
+        for after in update {
+            if relation.precedes(*before, *after) {
+                rules.entry(*before).or_default().push(*after);
+            }
+        }
+    }
+
+    // reach[i][j] when some chain of rules within the update puts page i before page j
+    let mut reach: Vec<Vec<bool>> = update
+        .iter()
+        .map(|before| {
+            update
+                .iter()
+                .map(|after| relation.precedes(*before, *after))
+                .collect()
+        })
+        .collect();
+    for k in 0..n {
+        let through = reach[k].clone();
+        for row in reach.iter_mut().filter(|row| row[k]) {
+            for (cell, via) in row.iter_mut().zip(&through) {
+                *cell |= via;
+            }
+        }
+    }
+
+    if (0..n).any(|i| reach[i][i]) {
+        return Err(order::topological_sort(&rules, update).unwrap_err());
+    }
+
+    let kept = largest_consistent(&reach);
+
+    for pair in kept.windows(2) {
+        let (before, after) = (update[pair[0]], update[pair[1]]);
+        rules.entry(before).or_default().push(after);
+    }
+    let fixed = order::topological_sort(&rules, update)?;
+
+    Ok(Fix {
+        moves: moves(update, &kept, &fixed),
+        fixed,
+    })
+}
+
+// Moves that turn the update into the fixed order when applied one after another,
+// each taking the page out at `from` and putting it back at `to`. Pages are moved
+// in their fixed order, each right after the closest page before it in the fixed
+// order that is already in place, so the pages in place always keep that order
+fn moves(update: &[i64], kept: &[usize], fixed: &[i64]) -> Vec<Move> {
+    let mut current = update.to_vec();
+    let mut placed: Vec<bool> = fixed
+        .iter()
+        .map(|page| kept.iter().any(|index| update[*index] == *page))
+        .collect();
+
+    let mut moves = Vec::new();
+    for (target, page) in fixed.iter().enumerate() {
+        if placed[target] {
+            continue;
+        }
+
+        let from = current.iter().position(|other| other == page).unwrap();
+        current.remove(from);
+
+        let to = match (0..target).rev().find(|index| placed[*index]) {
+            Some(previous) => {
+                current
+                    .iter()
+                    .position(|other| *other == fixed[previous])
+                    .unwrap()
+                    + 1
+            }
+            None => 0,
+        };
+        current.insert(to, *page);
+        placed[target] = true;
+
+        moves.push(Move {
+            page: *page,
+            from,
+            to,
+        });
+    }
+
+    moves
+}
+
+// Kuhn's augmenting path search for the matching below
+fn augment(
+    left: usize,
+    out_of_order: &impl Fn(usize, usize) -> bool,
+    seen: &mut [bool],
+    matched_left: &mut [Option<usize>],
+    matched_right: &mut [Option<usize>],
+) -> bool {
+    for right in 0..seen.len() {
+        if !out_of_order(left, right) || seen[right] {
+            continue;
+        }
+        seen[right] = true;
+
+        let free = match matched_right[right] {
+            None => true,
+            Some(other) => augment(other, out_of_order, seen, matched_left, matched_right),
+        };
+        if free {
+            matched_right[right] = Some(left);
+            matched_left[left] = Some(right);
+            return true;
+        }
+    }
+
+    false
+}
+
+// Pairs i < j with reach[j][i] are out of order, and being out of order is
+// transitive, so this is a partial order on positions. The pages that can stay
+// are a largest antichain of it, found from a maximum matching by König's theorem
+fn largest_consistent(reach: &[Vec<bool>]) -> Vec<usize> {
+    let n = reach.len();
+    let out_of_order = |i: usize, j: usize| i < j && reach[j][i];
+
+    let mut matched_right: Vec<Option<usize>> = vec![None; n];
+    let mut matched_left: Vec<Option<usize>> = vec![None; n];
+
+    for left in 0..n {
+        let mut seen = vec![false; n];
+        augment(
+            left,
+            &out_of_order,
+            &mut seen,
+            &mut matched_left,
+            &mut matched_right,
+        );
+    }
+
+    // Alternating search from the unmatched left vertices
+    let mut visited_left = vec![false; n];
+    let mut visited_right = vec![false; n];
+    let mut stack: Vec<usize> = (0..n).filter(|i| matched_left[*i].is_none()).collect();
+    for left in &stack {
+        visited_left[*left] = true;
+    }
+    while let Some(left) = stack.pop() {
+        for right in 0..n {
+            if out_of_order(left, right) && !visited_right[right] {
+                visited_right[right] = true;
+                if let Some(next) = matched_right[right] {
+                    if !visited_left[next] {
+                        visited_left[next] = true;
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+    }
+
+    (0..n)
+        .filter(|i| visited_left[*i] && !visited_right[*i])
+        .collect()
+}
+
+fn join(pages: &[i64]) -> String {
+    pages
+        .iter()
+        .map(|page| page.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Update {}: {}", self.index, join(&self.update))?;
+
+        for violation in &self.violations {
+            writeln!(
+                f,
+                "  breaks {}|{} ({} at {}, {} at {})",
+                violation.before,
+                violation.after,
+                violation.before,
+                violation.before_position,
+                violation.after,
+                violation.after_position
+            )?;
+        }
+
+        match &self.fix {
+            Ok(fix) => {
+                for step in &fix.moves {
+                    writeln!(f, "  move {} from {} to {}", step.page, step.from, step.to)?;
+                }
+                write!(f, "  fixed: {}", join(&fix.fixed))
+            }
+            Err(err) => write!(f, "  cannot be fixed, {}", err),
+        }
+    }
+}
+
+impl Explanation {
+    pub fn to_json(&self) -> String {
+        let violations: Vec<String> = self
+            .violations
+            .iter()
+            .map(|violation| {
+                format!(
+                    r#"{{"rule":[{},{}],"positions":[{},{}]}}"#,
+                    violation.before,
+                    violation.after,
+                    violation.before_position,
+                    violation.after_position
+                )
+            })
+            .collect();
+
+        let fix = match &self.fix {
+            Ok(fix) => {
+                let moves: Vec<String> = fix
+                    .moves
+                    .iter()
+                    .map(|step| {
+                        format!(
+                            r#"{{"page":{},"from":{},"to":{}}}"#,
+                            step.page, step.from, step.to
+                        )
+                    })
+                    .collect();
+
+                format!(
+                    r#""moves":[{}],"fixed":[{}]"#,
+                    moves.join(","),
+                    join(&fix.fixed)
+                )
+            }
+            Err(err) => format!(r#""cycle":[{}]"#, join(&err.pages)),
+        };
+
+        format!(
+            r#"{{"update":{},"pages":[{}],"violations":[{}],{}}}"#,
+            self.index,
+            join(&self.update),
+            violations.join(","),
+            fix
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules_from_pairs;
+
+    fn permutations(pages: &[i64]) -> Vec<Vec<i64>> {
+        if pages.is_empty() {
+            return vec![Vec::new()];
+        }
+
+        (0..pages.len())
+            .flat_map(|index| {
+                let mut rest = pages.to_vec();
+                let page = rest.remove(index);
+                permutations(&rest).into_iter().map(move |mut tail| {
+                    tail.insert(0, page);
+                    tail
+                })
+            })
+            .collect()
+    }
+
+    fn longest_common(a: &[i64], b: &[i64]) -> usize {
+        let mut lengths = vec![vec![0; b.len() + 1]; a.len() + 1];
+        for (i, x) in a.iter().enumerate() {
+            for (j, y) in b.iter().enumerate() {
+                lengths[i + 1][j + 1] = if x == y {
+                    lengths[i][j] + 1
+                } else {
+                    lengths[i][j + 1].max(lengths[i + 1][j])
+                };
+            }
+        }
+        lengths[a.len()][b.len()]
+    }
+
+    fn apply(update: &[i64], moves: &[Move]) -> Vec<i64> {
+        let mut pages = update.to_vec();
+        for step in moves {
+            assert_eq!(pages.remove(step.from), step.page);
+            assert_ne!(step.from, step.to);
+            pages.insert(step.to, step.page);
+        }
+        pages
+    }
+
+    // Every way of ordering each pair of four pages one way, the other or not at
+    // all. Moving one page keeps all others in order, so the fewest moves to any
+    // valid ordering leave its longest common subsequence with the update in place
+    #[test]
+    fn fewest_moves_on_every_relation() {
+        let update = [30, 10, 40, 20];
+        let pairs: Vec<(i64, i64)> = (0..4)
+            .flat_map(|i| (i + 1..4).map(move |j| (update[i], update[j])))
+            .collect();
+
+        for code in 0..3_usize.pow(pairs.len() as u32) {
+            let rules: Vec<(i64, i64)> = pairs
+                .iter()
+                .enumerate()
+                .filter_map(
+                    |(index, &(a, b))| match code / 3_usize.pow(index as u32) % 3 {
+                        0 => None,
+                        1 => Some((a, b)),
+                        _ => Some((b, a)),
+                    },
+                )
+                .collect();
+            let relation = Relation::new(&rules_from_pairs(&rules));
+
+            let fewest = permutations(&update)
+                .iter()
+                .filter(|ordering| relation.is_ordered(ordering))
+                .map(|ordering| update.len() - longest_common(&update, ordering))
+                .min();
+
+            match (fix(&relation, &update), fewest) {
+                (Ok(fix), Some(fewest)) => {
+                    assert_eq!(fix.moves.len(), fewest, "rules {:?}", rules);
+                    assert!(relation.is_ordered(&fix.fixed), "rules {:?}", rules);
+                    assert_eq!(apply(&update, &fix.moves), fix.fixed, "rules {:?}", rules);
+                }
+                (Err(_), None) => {}
+                (fix, fewest) => panic!("rules {:?}: {:?} but {:?}", rules, fix, fewest),
+            }
+        }
+    }
+
+    #[test]
+    fn largest_consistent_positions() {
+        // Positions 1 and 2 must both come before position 0
+        let reach = vec![
+            vec![false, false, false],
+            vec![true, false, false],
+            vec![true, false, false],
+        ];
+        assert_eq!(largest_consistent(&reach), vec![1, 2]);
+
+        // Reversed chain, only one position can stay
+        let reach = vec![
+            vec![false, false, false],
+            vec![true, false, false],
+            vec![true, true, false],
+        ];
+        assert_eq!(largest_consistent(&reach).len(), 1);
+
+        assert!(largest_consistent(&[]).is_empty());
+    }
+
+    #[test]
+    fn moves_apply_in_order() {
+        let relation = Relation::new(&rules_from_pairs(&[
+            (97, 75),
+            (75, 47),
+            (47, 61),
+            (61, 53),
+            (97, 13),
+        ]));
+        let explanation = explain(&relation, 3, &[61, 53, 13, 47, 75, 97]);
+
+        let fix = explanation.fix.as_ref().unwrap();
+        assert_eq!(fix.moves.len(), 3);
+        assert_eq!(apply(&explanation.update, &fix.moves), fix.fixed);
+        assert_eq!(
+            explanation.to_string().lines().last(),
+            Some("  fixed: 97,75,47,61,53,13")
+        );
+    }
+
+    #[test]
+    fn cycles_cannot_be_fixed() {
+        let relation = Relation::new(&rules_from_pairs(&[(1, 2), (2, 3), (3, 1), (4, 1)]));
+        let explanation = explain(&relation, 0, &[4, 3, 2, 1, 5]);
+
+        let err = explanation.fix.as_ref().unwrap_err();
+        let mut pages = err.pages.clone();
+        pages.sort_unstable();
+        assert_eq!(pages, vec![1, 2, 3]);
+
+        assert!(explanation
+            .to_string()
+            .ends_with("  cannot be fixed, rules form a cycle: 1 -> 2 -> 3 -> 1"));
+        assert!(explanation.to_json().ends_with(r#""cycle":[1,2,3]}"#));
+    }
+}
@@ -1,24 +1,26 @@
+mod dot;
+mod explain;
+mod order;
+mod orderings;
+mod relation;
+mod ruleset;
 use std::{
     collections::HashMap,
     fs::File,
-    io::{BufRead, BufReader},
+    io::{self, BufRead, BufReader, Read},
 };
 
+use order::CycleError;
+use relation::Relation;
+use ruleset::RuleSet;
+
 #[derive(Debug)]
 struct Data {
     rules: HashMap<i64, Vec<i64>>,
+    relation: Relation,
     updates: Vec<Vec<i64>>,
 }
 
-impl Data {
-    fn new() -> Self {
-        Self {
-            rules: HashMap::new(),
-            updates: Vec::new(),
-        }
-    }
-}
-
 fn load_input(path: &'static str) -> Data {
     let file = match File::open(path) {
         Ok(x) => x,
@@ -30,115 +32,322 @@ fn load_input(path: &'static str) -> Data {
         }
     };
 
-    let file_reader = BufReader::new(file);
+    let mut file_reader = BufReader::new(file);
 
-    let mut data = Data::new();
+    let mut text = String::new();
+    file_reader.read_to_string(&mut text).unwrap();
 
-    let mut rules = true;
+    parse_input(&text).unwrap_or_else(|err| panic!("Invalid input: {}", err))
+}
 
-    for line in file_reader.lines().filter_map(|e| e.ok()) {
-        if rules {
-            if line == "" {
-                rules = false;
-            } else {
-                let rule: Vec<i64> = line.split("|").map(|e| e.parse::<i64>().unwrap()).collect();
+fn parse_number(text: &str) -> Option<i64> {
+    text.trim().parse().ok()
+}
+
+// Rules come first and updates after them. Lines are trimmed, so CRLF endings and
+// whitespace-only lines are fine, and blank lines anywhere are skipped. The first
+// line that is not a rule starts the updates, even without a separator before it,
+// unless it cannot be an update either and is still among the rules
+fn parse_input(text: &str) -> Result<Data, String> {
+    let mut rules: HashMap<i64, Vec<i64>> = HashMap::new();
+    let mut updates: Vec<Vec<i64>> = Vec::new();
+    // A blank line after the rules
+    let mut separated = false;
 
-                data.rules.entry(rule[0]).or_default().push(rule[1]);
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+
+        if line.is_empty() {
+            separated |= !rules.is_empty();
+            continue;
+        }
+
+        let among_rules = updates.is_empty() && !separated;
+        let malformed_rule = among_rules && !line.contains(',') && parse_number(line).is_none();
+
+        if line.contains('|') || malformed_rule {
+            if !updates.is_empty() {
+                return Err(format!("line {}: rule after the updates", line_number));
+            }
+
+            let rule = parse_rule(line)
+                .ok_or_else(|| format!("line {}: invalid rule \"{}\"", line_number, line))?;
+
+            if rule.0 == rule.1 {
+                return Err(format!(
+                    "line {}: rule \"{}\" puts a page before itself",
+                    line_number, line
+                ));
             }
+
+            rules.entry(rule.0).or_default().push(rule.1);
         } else {
-            let update: Vec<i64> = line.split(",").map(|e| e.parse::<i64>().unwrap()).collect();
+            let update: Vec<i64> = line
+                .split(',')
+                .map(|page| {
+                    parse_number(page).ok_or_else(|| {
+                        format!("line {}: invalid page \"{}\" in update", line_number, page)
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+
+            if update.len().is_multiple_of(2) {
+                return Err(format!(
+                    "line {}: update has {} pages, so it has no middle page",
+                    line_number,
+                    update.len()
+                ));
+            }
 
-            data.updates.push(update);
+            updates.push(update);
         }
     }
 
-    data
+    Ok(Data {
+        relation: Relation::new(&rules),
+        rules,
+        updates,
+    })
 }
 
 fn part1(data: &Data) -> i64 {
-    let mut previous_pages: Vec<i64> = Vec::new();
-
     data.updates
         .iter()
-        .filter_map(|update| {
-            previous_pages.clear();
-            let found = update.iter().any(|page| {
-                let value = data
-                    .rules
-                    .get(page)
-                    .unwrap_or(&Vec::new())
-                    .iter()
-                    .any(|e| previous_pages.contains(e));
-
-                previous_pages.push(*page);
-
-                value
-            });
-
-            if !found {
-                Some(update[update.len() / 2])
-            } else {
-                None
-            }
-        })
+        .filter(|update| data.relation.is_ordered(update))
+        .map(|update| update[update.len() / 2])
         .sum()
 }
 
-fn part2(data: &Data) -> i64 {
-    let mut previous_pages: Vec<i64> = Vec::new();
-
+fn part2(data: &Data) -> Result<i64, CycleError> {
     data.updates
         .iter()
-        .filter_map(|update| {
-            previous_pages.clear();
-            let found = update.iter().any(|page| {
-                let value = data
-                    .rules
-                    .get(page)
-                    .unwrap_or(&Vec::new())
-                    .iter()
-                    .any(|e| previous_pages.contains(e));
-
-                previous_pages.push(*page);
-
-                value
-            });
-
-            if !found {
-                None
+        .filter(|update| !data.relation.is_ordered(update))
+        .map(|update| {
+            // Sorting needs a total order, anything weaker goes through the
+            // topological sort, which also reports cycles
+            let fixed_update = if data.relation.is_total_on(update) {
+                let mut fixed_update = update.clone();
+                fixed_update.sort_by(|a, b| data.relation.compare(*a, *b));
+                fixed_update
             } else {
-                let mut fixed_update: Vec<i64> = Vec::new();
-
-                update.iter().for_each(|page| {
-                    let earliest_occurence = data
-                        .rules
-                        .get(page)
-                        .unwrap_or(&Vec::new())
-                        .iter()
-                        .fold(fixed_update.len(), |earliest_occurence, rule| {
-                            if let Some(position) = fixed_update.iter().position(|e| e == rule) {
-                                if position < earliest_occurence {
-                                    position
-                                } else {
-                                    earliest_occurence
-                                }
-                            } else {
-                                earliest_occurence
-                            }
-                        });
-
-                    fixed_update.insert(earliest_occurence, *page);
-                });
-
-                Some(fixed_update[fixed_update.len() / 2])
-            }
+                order::topological_sort(&data.rules, update)?
+            };
+
+            Ok(fixed_update[fixed_update.len() / 2])
         })
         .sum()
 }
 
+fn parse_rule(text: &str) -> Option<(i64, i64)> {
+    let (before, after) = text.split_once('|')?;
+    Some((parse_number(before)?, parse_number(after)?))
+}
+
+// Rules as parse_input stores them, for tests
+#[cfg(test)]
+fn rules_from_pairs(pairs: &[(i64, i64)]) -> HashMap<i64, Vec<i64>> {
+    let mut rules: HashMap<i64, Vec<i64>> = HashMap::new();
+    for (before, after) in pairs {
+        rules.entry(*before).or_default().push(*after);
+    }
+    rules
+}
+
+fn print_totals(rule_set: &RuleSet) {
+    println!("Part 1: {}", rule_set.part1());
+    match rule_set.part2() {
+        Ok(result) => println!("Part 2: {}", result),
+        Err(err) => println!("Part 2: cannot reorder updates, {}", err),
+    }
+}
+
+// Reads `+X|Y` to add a rule and `-X|Y` to remove one, one per line from stdin
+fn interactive(data: &Data) {
+    let mut rule_set = RuleSet::new(&data.rules, &data.updates);
+
+    for line in io::stdin().lock().lines().map_while(Result::ok) {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let flipped = if let Some((before, after)) = line
+            .strip_prefix('+')
+            .and_then(parse_rule)
+            .filter(|(before, after)| before != after)
+        {
+            rule_set.add(before, after)
+        } else if let Some((before, after)) = line.strip_prefix('-').and_then(parse_rule) {
+            rule_set.remove(before, after)
+        } else {
+            println!("Expected +X|Y or -X|Y, got \"{}\"", line);
+            continue;
+        };
+
+        for index in flipped {
+            println!(
+                "Update {} is now {}",
+                index,
+                if rule_set.is_valid(index) {
+                    "valid"
+                } else {
+                    "invalid"
+                }
+            );
+        }
+        print_totals(&rule_set);
+    }
+}
+
 fn main() {
     let data = load_input("./day5/input.txt");
 
-    println!("Part 1: {}", part1(&data));
-    println!("Part 2: {}", part2(&data));
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let value = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|index| args.get(index + 1))
+    };
+
+    let explain = args.iter().any(|arg| arg == "--explain");
+    let json = args.iter().any(|arg| arg == "--json");
+
+    // JSON output is kept machine readable, so it comes without the answers
+    if !(explain && json) {
+        println!("Part 1: {}", part1(&data));
+        match part2(&data) {
+            Ok(result) => println!("Part 2: {}", result),
+            Err(err) => println!("Part 2: cannot reorder updates, {}", err),
+        }
+    }
+
+    if explain {
+        let explanations: Vec<explain::Explanation> = data
+            .updates
+            .iter()
+            .enumerate()
+            .filter(|(_, update)| !data.relation.is_ordered(update))
+            .map(|(index, update)| explain::explain(&data.relation, index, update))
+            .collect();
+
+        if json {
+            let entries: Vec<String> = explanations.iter().map(|e| e.to_json()).collect();
+            println!("[{}]", entries.join(",\n"));
+        } else {
+            for explanation in explanations {
+                println!("{}", explanation);
+            }
+        }
+    }
+
+    // Part 2 takes the middle page of one fix, which is only well defined when no
+    // other valid ordering puts a different page in the middle
+    if args.iter().any(|arg| arg == "--orderings") {
+        let mut unique = 0;
+
+        for (index, update) in data.updates.iter().enumerate() {
+            let orderings = orderings::Orderings::new(&data.relation, update)
+                .unwrap_or_else(|err| panic!("Update {}: {}", index, err));
+
+            if orderings.is_unique() {
+                unique += 1;
+                continue;
+            }
+
+            let count = match orderings.count() {
+                Some(count) => count.to_string(),
+                None => "too many".to_owned(),
+            };
+            let candidates = orderings.middle_candidates();
+
+            println!(
+                "Update {}: {} orderings, middle page {}",
+                index,
+                count,
+                match candidates.len() {
+                    0 => "does not exist".to_owned(),
+                    1 => format!("is always {}", candidates[0]),
+                    _ => format!("could be any of {:?}", candidates),
+                }
+            );
+        }
+
+        println!(
+            "{} of {} updates have a unique valid ordering",
+            unique,
+            data.updates.len()
+        );
+    }
+
+    // The graph is written for a local GraphViz install, e.g. `dot -Tsvg rules.dot`
+    if let Some(path) = value("--dot") {
+        let explanation = value("--update").map(|index| {
+            let index: usize = index.parse().unwrap();
+            let update = data
+                .updates
+                .get(index)
+                .unwrap_or_else(|| panic!("There is no update {}", index));
+
+            explain::explain(&data.relation, index, update)
+        });
+
+        std::fs::write(path, dot::to_dot(&data.rules, explanation.as_ref())).unwrap();
+    }
+
+    if args.iter().any(|arg| arg == "--interactive") {
+        interactive(&data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "47|53\n97|13\n97|47\n\n75,47,61,53,29\n47,97,53\n";
+
+    fn answers(text: &str) -> (i64, i64) {
+        let data = parse_input(text).unwrap();
+        (part1(&data), part2(&data).unwrap())
+    }
+
+    #[test]
+    fn tolerates_line_endings_and_padding() {
+        let expected = answers(EXAMPLE);
+        assert_eq!(expected, (61, 47));
+
+        assert_eq!(answers(&EXAMPLE.replace('\n', "\r\n")), expected);
+        assert_eq!(answers(&EXAMPLE.replace("\n\n", "\n  \t\n")), expected);
+        assert_eq!(answers(&format!("\n{}\n\n \n", EXAMPLE)), expected);
+        assert_eq!(answers(&EXAMPLE.replace("\n\n", "\n")), expected);
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        let error = |text: &str| parse_input(text).unwrap_err();
+
+        assert!(error("47|53\n97|x\n\n75,47,61").starts_with("line 2:"));
+        assert!(error("47|53|61\n\n75,47,61").starts_with("line 1:"));
+        assert!(error("47|53\n\n75,,61").starts_with("line 3:"));
+        assert!(error("47|53\n\n75,47\n").starts_with("line 3:"));
+        assert!(error("47|53\n\n75,47,61\n97|13").starts_with("line 4:"));
+
+        // Lines among the rules that are neither a rule nor an update
+        assert_eq!(
+            error("47|53\n47/53\n\n75,47,61"),
+            "line 2: invalid rule \"47/53\""
+        );
+        assert_eq!(error("47 53\n\n75,47,61"), "line 1: invalid rule \"47 53\"");
+        assert_eq!(
+            error("47|53\n\n47/53"),
+            "line 3: invalid page \"47/53\" in update"
+        );
+        // Without a separator, a single page is still an update
+        assert_eq!(parse_input("47|53\n61\n").unwrap().updates, vec![vec![61]]);
+
+        assert_eq!(
+            error("47|53\n5|5\n\n5,6,7"),
+            "line 2: rule \"5|5\" puts a page before itself"
+        );
+    }
 }
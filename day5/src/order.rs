@@ -0,0 +1,175 @@
+use std::{collections::HashMap, fmt};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleError {
+    // Pages of the cycle in rule order, each one required before the next and the
+    // last one before the first
+    pub pages: Vec<i64>,
+}
+
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rules form a cycle: ")?;
+        for page in &self.pages {
+            write!(f, "{} -> ", page)?;
+        }
+        write!(f, "{}", self.pages[0])
+    }
+}
+
+// Kahn's algorithm over the rules whose both pages are in the update. Among the
+// pages that are free to go next, the one earliest in the update is taken, so
+// pages that no rule orders keep their relative order
+pub fn topological_sort(
+    rules: &HashMap<i64, Vec<i64>>,
+    update: &[i64],
+) -> Result<Vec<i64>, CycleError> {
+    let positions: HashMap<i64, usize> = update
+        .iter()
+        .enumerate()
+        .map(|(index, page)| (*page, index))
+        .collect();
+
+    let mut successors: Vec<Vec<usize>> = vec![Vec::new(); update.len()];
+    let mut in_degree: Vec<usize> = vec![0; update.len()];
+
+    for (index, page) in update.iter().enumerate() {
+        for after in rules.get(page).into_iter().flatten() {
+            if let Some(&after) = positions.get(after) {
+                successors[index].push(after);
+                in_degree[after] += 1;
+            }
+        }
+    }
+
+    let mut sorted: Vec<i64> = Vec::with_capacity(update.len());
+    let mut done = vec![false; update.len()];
+
+    while sorted.len() < update.len() {
+        let Some(next) = (0..update.len()).find(|&index| !done[index] && in_degree[index] == 0)
+        else {
+            return Err(find_cycle(update, &successors, &done));
+        };
+
+        done[next] = true;
+        sorted.push(update[next]);
+
+        for &after in &successors[next] {
+            in_degree[after] -= 1;
+        }
+    }
+
+    Ok(sorted)
+}
+
+// Every page left over by Kahn's algorithm has a predecessor that is also left
+// over, so walking predecessors from any of them must eventually repeat a page
+fn find_cycle(update: &[i64], successors: &[Vec<usize>], done: &[bool]) -> CycleError {
+    let mut predecessor: Vec<Option<usize>> = vec![None; update.len()];
+    for (index, after) in successors.iter().enumerate() {
+        for &after in after {
+            if !done[index] && !done[after] {
+                predecessor[after] = Some(index);
+            }
+        }
+    }
+
+    let mut current = done.iter().position(|done| !done).unwrap();
+    let mut seen = vec![false; update.len()];
+    while !seen[current] {
+        seen[current] = true;
+        current = predecessor[current].unwrap();
+    }
+
+    // `current` is on the cycle now, walk it once more to collect it
+    let mut cycle = vec![update[current]];
+    let mut page = predecessor[current].unwrap();
+    while page != current {
+        cycle.push(update[page]);
+        page = predecessor[page].unwrap();
+    }
+    cycle.reverse();
+
+    CycleError { pages: cycle }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules_from_pairs;
+
+    #[test]
+    fn unconstrained_pages_keep_their_order() {
+        assert_eq!(
+            topological_sort(&rules_from_pairs(&[]), &[5, 3, 9]),
+            Ok(vec![5, 3, 9])
+        );
+        assert_eq!(
+            topological_sort(&rules_from_pairs(&[(9, 3)]), &[3, 5, 9, 1]),
+            Ok(vec![5, 9, 3, 1])
+        );
+        // Rules about pages outside of the update are ignored
+        assert_eq!(
+            topological_sort(&rules_from_pairs(&[(7, 5), (3, 7)]), &[5, 3]),
+            Ok(vec![5, 3])
+        );
+    }
+
+    #[test]
+    fn example_fixes() {
+        let rules = rules_from_pairs(&[
+            (47, 53),
+            (97, 13),
+            (97, 61),
+            (97, 47),
+            (75, 29),
+            (61, 13),
+            (75, 53),
+            (29, 13),
+            (97, 29),
+            (53, 29),
+            (61, 53),
+            (97, 53),
+            (61, 29),
+            (47, 13),
+            (75, 47),
+            (97, 75),
+            (47, 61),
+            (75, 61),
+            (47, 29),
+            (75, 13),
+            (53, 13),
+        ]);
+
+        assert_eq!(
+            topological_sort(&rules, &[75, 97, 47, 61, 53]),
+            Ok(vec![97, 75, 47, 61, 53])
+        );
+        assert_eq!(
+            topological_sort(&rules, &[61, 13, 29]),
+            Ok(vec![61, 29, 13])
+        );
+        assert_eq!(
+            topological_sort(&rules, &[97, 13, 75, 29, 47]),
+            Ok(vec![97, 75, 47, 29, 13])
+        );
+    }
+
+    #[test]
+    fn cycle_lists_exactly_its_pages() {
+        let rules = rules_from_pairs(&[(1, 2), (2, 3), (3, 1), (4, 1), (3, 5)]);
+
+        let err = topological_sort(&rules, &[4, 3, 2, 1, 5]).unwrap_err();
+
+        let mut pages = err.pages.clone();
+        pages.sort_unstable();
+        assert_eq!(pages, vec![1, 2, 3]);
+
+        // Each page is required before the next one, and the last before the first
+        for (index, before) in err.pages.iter().enumerate() {
+            let after = err.pages[(index + 1) % err.pages.len()];
+            assert!(rules[before].contains(&after));
+        }
+        assert_eq!(err.to_string(), "rules form a cycle: 1 -> 2 -> 3 -> 1");
+    }
+}
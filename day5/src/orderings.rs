@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+
+use crate::relation::Relation;
+
+// Counting gives up once this many sets of pages could have been placed first
+const MAX_PREFIXES: usize = 1 << 20;
+
+// Orderings of a set of pages that satisfy every rule between them, with pages
+// identified by their index and sets of them kept as bitmasks
+#[derive(Debug, Clone)]
+pub struct Orderings {
+    pages: Vec<i64>,
+    // Pages that a rule puts directly before each page
+    predecessors: Vec<u64>,
+}
+
+impl Orderings {
+    pub fn new(relation: &Relation, pages: &[i64]) -> Result<Self, String> {
+        if pages.len() > 64 {
+            return Err(format!(
+                "cannot order {} pages, at most 64 are supported",
+                pages.len()
+            ));
+        }
+
+        let predecessors = pages
+            .iter()
+            .map(|after| {
+                pages
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, before)| relation.precedes(**before, *after))
+                    .fold(0, |mask, (index, _)| mask | 1 << index)
+            })
+            .collect();
+
+        Ok(Self {
+            pages: pages.to_vec(),
+            predecessors,
+        })
+    }
+
+    fn available(&self, placed: u64) -> impl Iterator<Item = usize> + '_ {
+        (0..self.pages.len()).filter(move |&index| {
+            placed & 1 << index == 0 && self.predecessors[index] & !placed == 0
+        })
+    }
+
+    // Number of valid orderings, by dynamic programming over the sets of pages
+    // that can be placed first. Only sets closed under the rules are reachable,
+    // which keeps this cheap whenever the rules are close to a total order. None
+    // when there are too many such sets or the count overflows
+    pub fn count(&self) -> Option<u128> {
+        let mut prefixes: HashMap<u64, u128> = HashMap::from([(0, 1)]);
+
+        for _ in 0..self.pages.len() {
+            let mut next: HashMap<u64, u128> = HashMap::new();
+
+            for (&placed, &ways) in &prefixes {
+                for index in self.available(placed) {
+                    let total = next.entry(placed | 1 << index).or_default();
+                    *total = total.checked_add(ways)?;
+                }
+            }
+
+            if next.len() > MAX_PREFIXES {
+                return None;
+            }
+            prefixes = next;
+        }
+
+        Some(prefixes.values().sum())
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            orderings: self,
+            chosen: Vec::new(),
+            placed: 0,
+            done: false,
+        }
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.iter().take(2).count() == 1
+    }
+
+    // Pages that sit in the middle of at least one valid ordering. A page can go
+    // anywhere between its number of ancestors and the number of pages minus its
+    // descendants, so no ordering needs to be built
+    pub fn middle_candidates(&self) -> Vec<i64> {
+        let n = self.pages.len();
+        if n == 0 || self.iter().next().is_none() {
+            return Vec::new();
+        }
+
+        // ancestors[i] holds every page that some chain of rules puts before page i
+        let mut ancestors = self.predecessors.clone();
+        loop {
+            let closed: Vec<u64> = ancestors
+                .iter()
+                .map(|&mask| {
+                    (0..n)
+                        .filter(|index| mask & 1 << index != 0)
+                        .fold(mask, |closed, index| closed | ancestors[index])
+                })
+                .collect();
+
+            if closed == ancestors {
+                break;
+            }
+            ancestors = closed;
+        }
+
+        let middle = n / 2;
+        (0..n)
+            .filter(|&index| {
+                let before = ancestors[index].count_ones() as usize;
+                let after = ancestors
+                    .iter()
+                    .filter(|&&mask| mask & 1 << index != 0)
+                    .count();
+
+                before <= middle && middle < n - after
+            })
+            .map(|index| self.pages[index])
+            .collect()
+    }
+}
+
+// Valid orderings, generated one at a time by backtracking. Any page whose
+// predecessors are all placed can go next without leading to a dead end, so every
+// step that is not the last makes progress towards the next ordering
+pub struct Iter<'a> {
+    orderings: &'a Orderings,
+    // Indices placed so far, in order
+    chosen: Vec<usize>,
+    placed: u64,
+    done: bool,
+}
+
+impl Iter<'_> {
+    // Places the first available page at or after index `from`, if there is one
+    fn place_from(&mut self, from: usize) -> bool {
+        match self
+            .orderings
+            .available(self.placed)
+            .find(|&index| index >= from)
+        {
+            Some(index) => {
+                self.chosen.push(index);
+                self.placed |= 1 << index;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Iterator for Iter<'_> {
+    type Item = Vec<i64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let n = self.orderings.pages.len();
+
+        if self.chosen.len() == n && n > 0 {
+            // Step back to the deepest position that has another page to try
+            loop {
+                let Some(last) = self.chosen.pop() else {
+                    self.done = true;
+                    return None;
+                };
+                self.placed &= !(1 << last);
+
+                if self.place_from(last + 1) {
+                    break;
+                }
+            }
+        }
+
+        while self.chosen.len() < n {
+            // Only a cycle among the remaining pages leaves nothing available
+            if !self.place_from(0) {
+                self.done = true;
+                return None;
+            }
+        }
+
+        if n == 0 {
+            self.done = true;
+        }
+
+        Some(
+            self.chosen
+                .iter()
+                .map(|&index| self.orderings.pages[index])
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::rules_from_pairs;
+
+    fn orderings(pairs: &[(i64, i64)], pages: &[i64]) -> Orderings {
+        Orderings::new(&Relation::new(&rules_from_pairs(pairs)), pages).unwrap()
+    }
+
+    #[test]
+    fn chain_is_unique() {
+        let chain = orderings(&[(1, 2), (2, 3), (3, 4), (4, 5)], &[4, 2, 5, 1, 3]);
+
+        assert_eq!(chain.count(), Some(1));
+        assert!(chain.is_unique());
+        assert_eq!(chain.iter().collect::<Vec<_>>(), vec![vec![1, 2, 3, 4, 5]]);
+        assert_eq!(chain.middle_candidates(), vec![3]);
+    }
+
+    #[test]
+    fn antichain_has_every_ordering() {
+        let antichain = orderings(&[], &[1, 2, 3, 4, 5]);
+
+        assert_eq!(antichain.count(), Some(120));
+        assert!(!antichain.is_unique());
+
+        let all: HashSet<Vec<i64>> = antichain.iter().collect();
+        assert_eq!(all.len(), 120);
+
+        let mut candidates = antichain.middle_candidates();
+        candidates.sort_unstable();
+        assert_eq!(candidates, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn cycle_has_no_orderings() {
+        let cycle = orderings(&[(1, 2), (2, 3), (3, 1)], &[1, 2, 3, 4, 5]);
+
+        assert_eq!(cycle.count(), Some(0));
+        assert_eq!(cycle.iter().next(), None);
+        assert!(!cycle.is_unique());
+        assert!(cycle.middle_candidates().is_empty());
+    }
+
+    #[test]
+    fn ambiguous_middle() {
+        // 1 goes first and 5 last, 3 comes before 4 and 2 can go anywhere between,
+        // so each of 2, 3 and 4 sits in the middle of some ordering
+        let pages = [1, 2, 3, 4, 5];
+        let rules = [(1, 2), (1, 3), (3, 4), (2, 5), (4, 5)];
+        let ambiguous = orderings(&rules, &pages);
+
+        let all: Vec<Vec<i64>> = ambiguous.iter().collect();
+        assert_eq!(
+            all,
+            vec![
+                vec![1, 2, 3, 4, 5],
+                vec![1, 3, 2, 4, 5],
+                vec![1, 3, 4, 2, 5]
+            ]
+        );
+        assert_eq!(ambiguous.count(), Some(3));
+
+        let middles: HashSet<i64> = all.iter().map(|ordering| ordering[2]).collect();
+        let candidates = ambiguous.middle_candidates();
+        assert_eq!(candidates, vec![2, 3, 4]);
+        assert_eq!(candidates.into_iter().collect::<HashSet<i64>>(), middles);
+    }
+
+    #[test]
+    fn page_limits() {
+        let pages: Vec<i64> = (0..65).collect();
+        assert!(Orderings::new(&Relation::new(&HashMap::new()), &pages).is_err());
+
+        let empty = orderings(&[], &[]);
+        assert_eq!(empty.count(), Some(1));
+        assert_eq!(empty.iter().collect::<Vec<_>>(), vec![Vec::<i64>::new()]);
+    }
+}
@@ -0,0 +1,258 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+// Pages below this limit are stored in a bit matrix, anything larger falls back
+// to a set of pairs
+const DENSE_LIMIT: i64 = 1024;
+
+#[derive(Debug, Clone)]
+pub enum Relation {
+    // Bit `before * size + after` is set when `before` must come before `after`
+    Dense { size: usize, bits: Vec<u64> },
+    Sparse(HashSet<(i64, i64)>),
+}
+
+impl Relation {
+    pub fn new(rules: &HashMap<i64, Vec<i64>>) -> Self {
+        let pairs = rules
+            .iter()
+            .flat_map(|(before, afters)| afters.iter().map(move |after| (*before, *after)));
+
+        let pages = rules
+            .iter()
+            .flat_map(|(before, afters)| afters.iter().chain([before]));
+
+        if pages.clone().all(|page| (0..DENSE_LIMIT).contains(page)) {
+            let size = pages.max().map_or(0, |largest| *largest as usize + 1);
+            let mut bits = vec![0; (size * size).div_ceil(64)];
+
+            for (before, after) in pairs {
+                let bit = before as usize * size + after as usize;
+                bits[bit / 64] |= 1 << (bit % 64);
+            }
+
+            Relation::Dense { size, bits }
+        } else {
+            Relation::Sparse(pairs.collect())
+        }
+    }
+
+    pub fn precedes(&self, before: i64, after: i64) -> bool {
+        match self {
+            Relation::Dense { size, bits } => {
+                let size = *size as i64;
+                if !(0..size).contains(&before) || !(0..size).contains(&after) {
+                    return false;
+                }
+
+                let bit = (before * size + after) as usize;
+                bits[bit / 64] & (1 << (bit % 64)) != 0
+            }
+            Relation::Sparse(pairs) => pairs.contains(&(before, after)),
+        }
+    }
+
+    // Both return whether the relation changed
+    pub fn insert(&mut self, before: i64, after: i64) -> bool {
+        if self.precedes(before, after) {
+            return false;
+        }
+
+        match self {
+            Relation::Dense { size, bits }
+                if (0..*size as i64).contains(&before) && (0..*size as i64).contains(&after) =>
+            {
+                let bit = before as usize * *size + after as usize;
+                bits[bit / 64] |= 1 << (bit % 64);
+            }
+            Relation::Dense { .. } => {
+                // The bit matrix has no room for the new page, so it is rebuilt
+                let mut rules: HashMap<i64, Vec<i64>> = HashMap::new();
+                for (before, after) in self.pairs().into_iter().chain([(before, after)]) {
+                    rules.entry(before).or_default().push(after);
+                }
+                *self = Relation::new(&rules);
+            }
+            Relation::Sparse(pairs) => {
+                pairs.insert((before, after));
+            }
+        }
+
+        true
+    }
+
+    pub fn remove(&mut self, before: i64, after: i64) -> bool {
+        if !self.precedes(before, after) {
+            return false;
+        }
+
+        match self {
+            Relation::Dense { size, bits } => {
+                let bit = before as usize * *size + after as usize;
+                bits[bit / 64] &= !(1 << (bit % 64));
+            }
+            Relation::Sparse(pairs) => {
+                pairs.remove(&(before, after));
+            }
+        }
+
+        true
+    }
+
+    fn pairs(&self) -> Vec<(i64, i64)> {
+        match self {
+            Relation::Dense { size, bits } => (0..size * size)
+                .filter(|bit| bits[bit / 64] & (1 << (bit % 64)) != 0)
+                .map(|bit| ((bit / size) as i64, (bit % size) as i64))
+                .collect(),
+            Relation::Sparse(pairs) => pairs.iter().copied().collect(),
+        }
+    }
+
+    // Pages that no rule orders compare as equal
+    pub fn compare(&self, a: i64, b: i64) -> Ordering {
+        if self.precedes(a, b) {
+            Ordering::Less
+        } else if self.precedes(b, a) {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }
+
+    pub fn is_ordered(&self, update: &[i64]) -> bool {
+        update.iter().enumerate().all(|(index, after)| {
+            update[..index]
+                .iter()
+                .all(|before| !self.precedes(*after, *before))
+        })
+    }
+
+    // True when the rules order every pair of pages one way and without cycles,
+    // which is exactly when `compare` is a total order on them
+    pub fn is_total_on(&self, update: &[i64]) -> bool {
+        let mut successors: Vec<usize> = Vec::with_capacity(update.len());
+
+        for (index, page) in update.iter().enumerate() {
+            let mut count = 0;
+
+            for (other_index, other) in update.iter().enumerate() {
+                if index == other_index {
+                    continue;
+                }
+
+                match (self.precedes(*page, *other), self.precedes(*other, *page)) {
+                    (true, false) => count += 1,
+                    (false, true) => {}
+                    _ => return false,
+                }
+            }
+
+            successors.push(count);
+        }
+
+        // A tournament is acyclic iff its pages have distinct numbers of successors
+        successors.sort_unstable();
+        successors
+            .iter()
+            .enumerate()
+            .all(|(index, count)| index == *count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules_from_pairs;
+
+    #[test]
+    fn dense_up_to_the_limit() {
+        let dense = Relation::new(&rules_from_pairs(&[(0, 1023), (1023, 5)]));
+        assert!(matches!(dense, Relation::Dense { size: 1024, .. }));
+
+        let sparse = Relation::new(&rules_from_pairs(&[(0, 1024), (1024, 5)]));
+        assert!(matches!(sparse, Relation::Sparse(_)));
+
+        for (relation, largest) in [(dense, 1023), (sparse, 1024)] {
+            assert!(relation.precedes(0, largest));
+            assert!(relation.precedes(largest, 5));
+            assert!(!relation.precedes(largest, 0));
+            assert!(!relation.precedes(0, 5));
+            // Pages outside of the bit matrix are never ordered
+            assert!(!relation.precedes(2000, 0));
+            assert!(!relation.precedes(0, -1));
+        }
+    }
+
+    #[test]
+    fn negative_pages() {
+        let relation = Relation::new(&rules_from_pairs(&[(-3, 4), (4, -7)]));
+        assert!(matches!(relation, Relation::Sparse(_)));
+
+        assert!(relation.precedes(-3, 4));
+        assert_eq!(relation.compare(4, -7), Ordering::Less);
+        assert_eq!(relation.compare(-7, 4), Ordering::Greater);
+        assert_eq!(relation.compare(-3, -7), Ordering::Equal);
+
+        assert!(relation.is_ordered(&[-3, 4, -7]));
+        assert!(!relation.is_ordered(&[4, -3, -7]));
+    }
+
+    #[test]
+    fn total_orders() {
+        let chain = Relation::new(&rules_from_pairs(&[(1, 2), (2, 3), (1, 3)]));
+        assert!(chain.is_total_on(&[3, 1, 2]));
+        assert!(chain.is_total_on(&[]));
+        // 4 is not ordered against anything
+        assert!(!chain.is_total_on(&[1, 2, 4]));
+
+        // Every pair is ordered, but around a cycle
+        let cycle = Relation::new(&rules_from_pairs(&[(1, 2), (2, 3), (3, 1)]));
+        assert!(!cycle.is_total_on(&[1, 2, 3]));
+        assert!(cycle.is_total_on(&[1, 2]));
+
+        // A cyclic tournament of four pages: 1 -> 2 -> 3 -> 1, all before 4
+        let tournament = Relation::new(&rules_from_pairs(&[
+            (1, 2),
+            (2, 3),
+            (3, 1),
+            (1, 4),
+            (2, 4),
+            (3, 4),
+        ]));
+        assert!(!tournament.is_total_on(&[1, 2, 3, 4]));
+
+        // Rules in both directions between the same pages
+        let both = Relation::new(&rules_from_pairs(&[(1, 2), (2, 1)]));
+        assert!(!both.is_total_on(&[1, 2]));
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut relation = Relation::new(&rules_from_pairs(&[(1, 2)]));
+
+        assert!(!relation.insert(1, 2));
+        assert!(relation.insert(2, 1));
+        // Pages past the bit matrix grow it, up to the limit
+        assert!(relation.insert(2, 1023));
+        assert!(matches!(relation, Relation::Dense { size: 1024, .. }));
+        assert!(relation.insert(-1, 1));
+        assert!(matches!(relation, Relation::Sparse(_)));
+
+        for (before, after) in [(1, 2), (2, 1), (2, 1023), (-1, 1)] {
+            assert!(relation.precedes(before, after));
+        }
+
+        assert!(relation.remove(2, 1));
+        assert!(!relation.remove(2, 1));
+        assert!(!relation.precedes(2, 1));
+        assert!(relation.precedes(1, 2));
+
+        let mut dense = Relation::new(&rules_from_pairs(&[(3, 4)]));
+        assert!(dense.remove(3, 4));
+        assert!(!dense.precedes(3, 4));
+        assert!(matches!(dense, Relation::Dense { .. }));
+    }
+}
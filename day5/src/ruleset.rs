@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+
+use crate::{
+    order::{self, CycleError},
+    relation::Relation,
+};
+
+// Rules that can change at runtime, with both answers kept up to date. A rule
+// only concerns the updates that contain both of its pages, so a change only
+// revisits those
+#[derive(Debug, Clone)]
+pub struct RuleSet {
+    rules: HashMap<i64, Vec<i64>>,
+    relation: Relation,
+    updates: Vec<Vec<i64>>,
+    positions: Vec<HashMap<i64, usize>>,
+    // Updates each page appears in
+    containing: HashMap<i64, Vec<usize>>,
+    // Number of rules each update breaks, it is valid when there are none
+    violations: Vec<usize>,
+    // Middle page of the fixed order of every invalid update
+    fixes: Vec<Option<Result<i64, CycleError>>>,
+    part1: i64,
+    part2: i64,
+    unfixable: usize,
+}
+
+impl RuleSet {
+    pub fn new(rules: &HashMap<i64, Vec<i64>>, updates: &[Vec<i64>]) -> Self {
+        let positions: Vec<HashMap<i64, usize>> = updates
+            .iter()
+            .map(|update| {
+                update
+                    .iter()
+                    .enumerate()
+                    .map(|(index, page)| (*page, index))
+                    .collect()
+            })
+            .collect();
+
+        let mut containing: HashMap<i64, Vec<usize>> = HashMap::new();
+        for (index, positions) in positions.iter().enumerate() {
+            for page in positions.keys() {
+                containing.entry(*page).or_default().push(index);
+            }
+        }
+
+        let mut rule_set = Self {
+            rules: HashMap::new(),
+            relation: Relation::new(rules),
+            updates: updates.to_vec(),
+            positions,
+            containing,
+            violations: vec![0; updates.len()],
+            fixes: vec![None; updates.len()],
+            part1: 0,
+            part2: 0,
+            unfixable: 0,
+        };
+
+        for (before, afters) in rules {
+            let known = rule_set.rules.entry(*before).or_default();
+            for after in afters {
+                if !known.contains(after) {
+                    known.push(*after);
+                }
+            }
+        }
+
+        for index in 0..updates.len() {
+            let update = &rule_set.updates[index];
+            rule_set.violations[index] = update
+                .iter()
+                .enumerate()
+                .map(|(position, after)| {
+                    update[position + 1..]
+                        .iter()
+                        .filter(|before| rule_set.relation.precedes(**before, *after))
+                        .count()
+                })
+                .sum();
+
+            if rule_set.is_valid(index) {
+                rule_set.part1 += rule_set.middle(index);
+            } else {
+                rule_set.refix(index);
+            }
+        }
+
+        rule_set
+    }
+
+    pub fn is_valid(&self, index: usize) -> bool {
+        self.violations[index] == 0
+    }
+
+    pub fn part1(&self) -> i64 {
+        self.part1
+    }
+
+    pub fn part2(&self) -> Result<i64, CycleError> {
+        if self.unfixable == 0 {
+            return Ok(self.part2);
+        }
+
+        let err = self
+            .fixes
+            .iter()
+            .flatten()
+            .find_map(|fix| fix.as_ref().err())
+            .unwrap();
+        Err(err.clone())
+    }
+
+    // Both return the indices of the updates that switched between valid and invalid
+    pub fn add(&mut self, before: i64, after: i64) -> Vec<usize> {
+        if !self.relation.insert(before, after) {
+            return Vec::new();
+        }
+        self.rules.entry(before).or_default().push(after);
+
+        self.revisit(before, after, true)
+    }
+
+    pub fn remove(&mut self, before: i64, after: i64) -> Vec<usize> {
+        if !self.relation.remove(before, after) {
+            return Vec::new();
+        }
+        if let Some(afters) = self.rules.get_mut(&before) {
+            afters.retain(|page| *page != after);
+        }
+
+        self.revisit(before, after, false)
+    }
+
+    fn revisit(&mut self, before: i64, after: i64, added: bool) -> Vec<usize> {
+        let affected: Vec<usize> = self
+            .containing
+            .get(&before)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|index| self.positions[*index].contains_key(&after))
+            .collect();
+
+        let mut flipped = Vec::new();
+
+        for index in affected {
+            let was_valid = self.is_valid(index);
+
+            if self.positions[index][&after] < self.positions[index][&before] {
+                if added {
+                    self.violations[index] += 1;
+                } else {
+                    self.violations[index] -= 1;
+                }
+            }
+
+            match (was_valid, self.is_valid(index)) {
+                (true, false) => self.part1 -= self.middle(index),
+                (false, true) => self.part1 += self.middle(index),
+                _ => {}
+            }
+
+            if was_valid != self.is_valid(index) {
+                flipped.push(index);
+            }
+
+            // Any rule between its pages can change how an invalid update is fixed
+            self.refix(index);
+        }
+
+        flipped
+    }
+
+    fn middle(&self, index: usize) -> i64 {
+        let update = &self.updates[index];
+        update[update.len() / 2]
+    }
+
+    fn refix(&mut self, index: usize) {
+        match self.fixes[index].take() {
+            Some(Ok(middle)) => self.part2 -= middle,
+            Some(Err(_)) => self.unfixable -= 1,
+            None => {}
+        }
+
+        if self.is_valid(index) {
+            return;
+        }
+
+        let fix = order::topological_sort(&self.rules, &self.updates[index])
+            .map(|fixed| fixed[fixed.len() / 2]);
+        match &fix {
+            Ok(middle) => self.part2 += middle,
+            Err(_) => self.unfixable += 1,
+        }
+        self.fixes[index] = Some(fix);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{part1, part2, rules_from_pairs, Data};
+
+    // Every change is checked against solving the updates from scratch
+    #[test]
+    fn rule_changes_match_a_full_recompute() {
+        let updates: Vec<Vec<i64>> = vec![
+            vec![10, 11, 12],
+            vec![12, 11, 10],
+            vec![13, 10, 15, 11, 14],
+            vec![15, 14, 13, 12, 11],
+            vec![10],
+            vec![11, 13, 15],
+            vec![14, 12, 10, 15, 13],
+            vec![12, 15, 11],
+        ];
+        let mut rules: Vec<(i64, i64)> = vec![(10, 11), (12, 13), (12, 13)];
+
+        let pairs: Vec<(i64, i64)> = (10..16)
+            .flat_map(|before| (10..16).map(move |after| (before, after)))
+            .filter(|(before, after)| before != after)
+            .collect();
+
+        let mut rule_set = RuleSet::new(&rules_from_pairs(&rules), &updates);
+        let mut valid: Vec<bool> = (0..updates.len())
+            .map(|index| rule_set.is_valid(index))
+            .collect();
+
+        // Goes over every pair four times in a scrambled order, removing on every
+        // fourth step, which lands on different pairs each time round
+        for step in 0..4 * pairs.len() {
+            let (before, after) = pairs[step * 7 % pairs.len()];
+
+            let flipped = if step % 4 == 3 {
+                rules.retain(|rule| *rule != (before, after));
+                rule_set.remove(before, after)
+            } else {
+                if !rules.contains(&(before, after)) {
+                    rules.push((before, after));
+                }
+                rule_set.add(before, after)
+            };
+
+            let rules = rules_from_pairs(&rules);
+            let expected = Data {
+                relation: Relation::new(&rules),
+                rules,
+                updates: updates.clone(),
+            };
+
+            let now_valid: Vec<bool> = updates
+                .iter()
+                .map(|update| expected.relation.is_ordered(update))
+                .collect();
+            let changed: Vec<usize> = (0..updates.len())
+                .filter(|index| valid[*index] != now_valid[*index])
+                .collect();
+            let mut flipped = flipped;
+            flipped.sort_unstable();
+            assert_eq!(flipped, changed, "step {}", step);
+            valid = now_valid;
+
+            for (index, valid) in valid.iter().enumerate() {
+                assert_eq!(rule_set.is_valid(index), *valid, "step {}", step);
+            }
+            assert_eq!(rule_set.part1(), part1(&expected), "step {}", step);
+            match part2(&expected) {
+                Ok(total) => assert_eq!(rule_set.part2(), Ok(total), "step {}", step),
+                Err(_) => assert!(rule_set.part2().is_err(), "step {}", step),
+            }
+        }
+    }
+}
@@ -1,9 +1,11 @@
 use std::{
     collections::HashSet,
     fs::File,
-    io::{BufRead, BufReader, Write},
+    io::{self, BufReader, Read},
 };
 
+use grid::{ImageFormat, Matrix, Rgb};
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 enum Direction {
     Up,
@@ -46,14 +48,63 @@ impl TryFrom<char> for Direction {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Tile {
+    #[default]
+    Empty,
+    Obstacle,
+    Guard(Direction),
+}
+
+impl Tile {
+    fn symbol(&self) -> char {
+        match self {
+            Tile::Empty => '.',
+            Tile::Obstacle => '#',
+            Tile::Guard(Direction::Up) => '^',
+            Tile::Guard(Direction::Down) => 'v',
+            Tile::Guard(Direction::Left) => '<',
+            Tile::Guard(Direction::Right) => '>',
+        }
+    }
+
+    fn colour(&self) -> Rgb {
+        match self {
+            Tile::Empty => [24, 24, 24],
+            Tile::Obstacle => [160, 160, 160],
+            Tile::Guard(_) => [230, 40, 40],
+        }
+    }
+}
+
+const PATH: Rgb = [255, 200, 0];
+
 #[derive(Debug, Clone)]
 struct Map {
-    obstacles: Vec<(i64, i64)>,
-    size: (i64, i64),
+    grid: Matrix<Tile>,
     guard_position: (i64, i64),
     guard_direction: Direction,
 }
 
+impl Map {
+    // Positions are (x, y), so x indexes columns and y indexes rows
+    fn tile(&self, (x, y): (i64, i64)) -> Option<&Tile> {
+        if x < 0 || y < 0 {
+            None
+        } else {
+            self.grid.get(y as usize, x as usize)
+        }
+    }
+
+    fn contains(&self, position: (i64, i64)) -> bool {
+        self.tile(position).is_some()
+    }
+
+    fn is_obstacle(&self, position: (i64, i64)) -> bool {
+        self.tile(position) == Some(&Tile::Obstacle)
+    }
+}
+
 fn load_input(path: &'static str) -> Map {
     let file = match File::open(path) {
         Ok(x) => x,
@@ -65,83 +116,68 @@ fn load_input(path: &'static str) -> Map {
         }
     };
 
-    let file_reader = BufReader::new(file);
-
-    let mut map = Map {
-        obstacles: Vec::new(),
-        size: (0, 0),
-        guard_position: (0, 0),
-        guard_direction: Direction::Up,
-    };
-
-    for (y, line) in file_reader.lines().filter_map(Result::ok).enumerate() {
-        map.size.1 = y as i64 + 1;
-        for (x, char) in line.chars().enumerate() {
-            map.size.0 = x as i64 + 1;
-            match char {
-                '#' => map.obstacles.push((x as i64, y as i64)),
-                '^' | '<' | '>' | 'v' => {
-                    map.guard_position = (x as i64, y as i64);
-                    map.guard_direction = Direction::try_from(char).unwrap();
-                }
-                _ => {}
-            }
-        }
+    let mut file_reader = BufReader::new(file);
+
+    let mut text = String::new();
+    file_reader.read_to_string(&mut text).unwrap();
+
+    let grid = Matrix::parse(&text, |c| match c {
+        '#' => Tile::Obstacle,
+        _ => match Direction::try_from(c) {
+            Ok(direction) => Tile::Guard(direction),
+            Err(_) => Tile::Empty,
+        },
+    })
+    .unwrap_or_else(|err| panic!("Invalid input: {}", err));
+
+    let ((y, x), guard_direction) = grid
+        .cells()
+        .find_map(|(position, tile)| match tile {
+            Tile::Guard(direction) => Some((position, *direction)),
+            _ => None,
+        })
+        .expect("The map has no guard on it");
+
+    Map {
+        guard_position: (x as i64, y as i64),
+        guard_direction,
+        grid,
     }
-
-    map
 }
 
-fn part1(map: &Map) -> i64 {
+// Positions the guard visits before leaving the map
+fn guard_path(map: &Map) -> HashSet<(i64, i64)> {
     let mut current_guard_position = map.guard_position;
     let mut current_guard_direction = map.guard_direction;
 
     let mut visited_positions: HashSet<(i64, i64)> = HashSet::new();
 
-    while current_guard_position.0 >= 0
-        && current_guard_position.0 < map.size.0
-        && current_guard_position.1 >= 0
-        && current_guard_position.1 < map.size.1
-    {
+    while map.contains(current_guard_position) {
         visited_positions.insert(current_guard_position);
 
         let dir_vector = current_guard_direction.vector();
         let new_x = current_guard_position.0 + dir_vector.0;
         let new_y = current_guard_position.1 + dir_vector.1;
 
-        if map.obstacles.contains(&(new_x, new_y)) {
+        if map.is_obstacle((new_x, new_y)) {
             current_guard_direction = current_guard_direction.next_direction();
         } else {
             current_guard_position = (new_x, new_y);
         }
     }
 
-    visited_positions.len() as i64
+    visited_positions
 }
 
-fn part2(map: &Map) -> i64 {
-    let mut current_guard_position = map.guard_position;
-    let mut current_guard_direction = map.guard_direction;
-
-    let mut visited_positions: HashSet<(i64, i64)> = HashSet::new();
-
-    while current_guard_position.0 >= 0
-        && current_guard_position.0 < map.size.0
-        && current_guard_position.1 >= 0
-        && current_guard_position.1 < map.size.1
-    {
-        visited_positions.insert(current_guard_position);
+fn part1(map: &Map) -> i64 {
+    guard_path(map).len() as i64
+}
 
-        let dir_vector = current_guard_direction.vector();
-        let new_x = current_guard_position.0 + dir_vector.0;
-        let new_y = current_guard_position.1 + dir_vector.1;
+fn part2(map: &Map) -> i64 {
+    let visited_positions = guard_path(map);
 
-        if map.obstacles.contains(&(new_x, new_y)) {
-            current_guard_direction = current_guard_direction.next_direction();
-        } else {
-            current_guard_position = (new_x, new_y);
-        }
-    }
+    let mut current_guard_position;
+    let mut current_guard_direction;
 
     let mut visited_positions_dir: HashSet<(i64, i64, Direction)> = HashSet::new();
 
@@ -153,11 +189,7 @@ fn part2(map: &Map) -> i64 {
 
         visited_positions_dir.clear();
 
-        while current_guard_position.0 >= 0
-            && current_guard_position.0 < map.size.0
-            && current_guard_position.1 >= 0
-            && current_guard_position.1 < map.size.1
-        {
+        while map.contains(current_guard_position) {
             let visited_dir = (
                 current_guard_position.0,
                 current_guard_position.1,
@@ -175,7 +207,7 @@ fn part2(map: &Map) -> i64 {
             let new_x = current_guard_position.0 + dir_vector.0;
             let new_y = current_guard_position.1 + dir_vector.1;
 
-            if map.obstacles.contains(&(new_x, new_y))
+            if map.is_obstacle((new_x, new_y))
                 || (new_x == new_obstacle.0 && new_y == new_obstacle.1)
             {
                 current_guard_direction = current_guard_direction.next_direction();
@@ -193,4 +225,49 @@ fn main() {
 
     println!("Part 1: {}", part1(&map));
     println!("Part 2: {}", part2(&map));
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let value = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|index| args.get(index + 1))
+    };
+
+    let show = args.iter().any(|arg| arg == "--show");
+    let image = value("--image");
+
+    if show || image.is_some() {
+        let path = guard_path(&map);
+        let on_path = |(row, column): (usize, usize), tile: &Tile| {
+            *tile == Tile::Empty && path.contains(&(column as i64, row as i64))
+        };
+
+        if show {
+            map.grid
+                .render_ansi(&mut io::stdout().lock(), Tile::symbol, |position, tile| {
+                    on_path(position, tile).then_some(PATH)
+                })
+                .unwrap();
+        }
+
+        if let Some(image) = image {
+            let scale = value("--scale").map_or(4, |scale| scale.parse().unwrap());
+            let mut file = File::create(image).unwrap();
+
+            map.grid
+                .render_image(
+                    &mut file,
+                    ImageFormat::from_path(image),
+                    scale,
+                    |position, tile| {
+                        if on_path(position, tile) {
+                            PATH
+                        } else {
+                            tile.colour()
+                        }
+                    },
+                )
+                .unwrap();
+        }
+    }
 }
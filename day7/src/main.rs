@@ -23,7 +23,7 @@ fn load_input(path: &'static str) -> Vec<Equation> {
 
     let mut equations: Vec<Equation> = Vec::new();
 
-    for line in file_reader.lines().filter_map(Result::ok) {
+    for line in file_reader.lines().map_while(Result::ok) {
         let (value_str, operators_str) = line.split_once(':').unwrap();
         let value = value_str.parse::<i64>().unwrap();
         let operators: Vec<i64> = operators_str
@@ -0,0 +1,10 @@
+mod line;
+mod matrix;
+mod render;
+mod transform;
+mod view;
+
+pub use line::Line;
+pub use matrix::{Matrix, MatrixError};
+pub use render::{ImageFormat, Rgb};
+pub use view::{View, Windows};
@@ -0,0 +1,56 @@
+// A lazy view of `len` cells of a matrix, `stride` apart, starting at `start`. It
+// borrows the matrix data directly, so walking a line never allocates.
+#[derive(Debug, Clone)]
+pub struct Line<'a, T> {
+    data: &'a [T],
+    start: usize,
+    stride: usize,
+    len: usize,
+}
+
+impl<'a, T> Line<'a, T> {
+    pub(crate) fn new(data: &'a [T], start: usize, stride: usize, len: usize) -> Self {
+        debug_assert!(len == 0 || start + (len - 1) * stride < data.len());
+
+        Self {
+            data,
+            start,
+            stride,
+            len,
+        }
+    }
+}
+
+impl<'a, T> Iterator for Line<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            None
+        } else {
+            let out = &self.data[self.start];
+            self.start += self.stride;
+            self.len -= 1;
+
+            Some(out)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Line<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            None
+        } else {
+            self.len -= 1;
+
+            Some(&self.data[self.start + self.len * self.stride])
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for Line<'_, T> {}
@@ -0,0 +1,737 @@
+use std::{
+    fmt,
+    ops::{Index, IndexMut},
+};
+
+use crate::{Line, View, Windows};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatrixError {
+    Empty,
+    // 1-based line number of the first line whose length differs from line 1
+    Ragged {
+        line: usize,
+        length: usize,
+        expected: usize,
+    },
+    SizeMismatch {
+        cells: usize,
+        rows: usize,
+        columns: usize,
+    },
+}
+
+impl fmt::Display for MatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatrixError::Empty => write!(f, "input is empty"),
+            MatrixError::Ragged {
+                line,
+                length,
+                expected,
+            } => write!(
+                f,
+                "line {} has {} cells, expected {} like line 1",
+                line, length, expected
+            ),
+            MatrixError::SizeMismatch {
+                cells,
+                rows,
+                columns,
+            } => write!(
+                f,
+                "{} cells cannot fill a {}x{} matrix, expected {}",
+                cells,
+                rows,
+                columns,
+                rows * columns
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Matrix<T: Default + Clone> {
+    data: Vec<T>,
+    rows: usize,
+    columns: usize,
+}
+
+impl<'a, T: Default + Clone> Matrix<T> {
+    pub fn new(rows: usize, columns: usize) -> Self {
+        Self {
+            data: vec![T::default(); rows * columns],
+            rows,
+            columns,
+        }
+    }
+
+    pub fn from_vec(rows: usize, columns: usize, data: Vec<T>) -> Result<Self, MatrixError> {
+        if data.len() != rows * columns {
+            return Err(MatrixError::SizeMismatch {
+                cells: data.len(),
+                rows,
+                columns,
+            });
+        }
+
+        Ok(Self {
+            data,
+            rows,
+            columns,
+        })
+    }
+
+    pub fn from_fn(rows: usize, columns: usize, mut f: impl FnMut(usize, usize) -> T) -> Self {
+        Self {
+            data: (0..rows * columns)
+                .map(|index| f(index / columns, index % columns))
+                .collect(),
+            rows,
+            columns,
+        }
+    }
+
+    // One row per line of `text`, one cell per character. Trailing blank lines are
+    // ignored, but the input must not be empty and every line must be equally long.
+    pub fn parse(text: &str, f: impl Fn(char) -> T) -> Result<Self, MatrixError> {
+        let mut lines: Vec<&str> = text.lines().collect();
+        while lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
+        }
+
+        let columns = match lines.first() {
+            Some(line) => line.chars().count(),
+            None => return Err(MatrixError::Empty),
+        };
+
+        let mut data: Vec<T> = Vec::with_capacity(lines.len() * columns);
+
+        for (index, line) in lines.iter().enumerate() {
+            let length = line.chars().count();
+            if length != columns {
+                return Err(MatrixError::Ragged {
+                    line: index + 1,
+                    length,
+                    expected: columns,
+                });
+            }
+
+            data.extend(line.chars().map(&f));
+        }
+
+        Self::from_vec(lines.len(), columns, data)
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.columns
+    }
+
+    pub fn data(&self) -> &Vec<T> {
+        &self.data
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> Option<&T> {
+        if row < self.rows && column < self.columns {
+            self.data.get(row * self.columns + column)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, row: usize, column: usize) -> Option<&mut T> {
+        if row < self.rows && column < self.columns {
+            self.data.get_mut(row * self.columns + column)
+        } else {
+            None
+        }
+    }
+
+    // (row, column) of the cell at `index` in `data()`
+    pub fn position(&self, index: usize) -> Option<(usize, usize)> {
+        if index < self.data.len() {
+            Some((index / self.columns, index % self.columns))
+        } else {
+            None
+        }
+    }
+
+    // Every cell together with its (row, column)
+    pub fn cells(&self) -> impl Iterator<Item = ((usize, usize), &T)> + '_ {
+        self.data
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| ((index / self.columns, index % self.columns), cell))
+    }
+
+    // Orthogonal neighbours that lie inside the grid
+    pub fn neighbours4(
+        &self,
+        row: usize,
+        column: usize,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.neighbours(row, column, &[(-1, 0), (0, 1), (1, 0), (0, -1)])
+    }
+
+    // Orthogonal and diagonal neighbours that lie inside the grid
+    pub fn neighbours8(
+        &self,
+        row: usize,
+        column: usize,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.neighbours(
+            row,
+            column,
+            &[
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+                (1, 0),
+                (1, -1),
+                (0, -1),
+            ],
+        )
+    }
+
+    fn neighbours(
+        &self,
+        row: usize,
+        column: usize,
+        offsets: &'static [(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        offsets
+            .iter()
+            .filter_map(move |&(row_offset, column_offset)| {
+                let row = row.checked_add_signed(row_offset)?;
+                let column = column.checked_add_signed(column_offset)?;
+
+                self.get(row, column).map(|_| (row, column))
+            })
+    }
+
+    pub fn row(&'a self, row_index: usize) -> Option<Line<'a, T>> {
+        if row_index >= self.rows {
+            None
+        } else {
+            Some(Line::new(
+                &self.data,
+                row_index * self.columns,
+                1,
+                self.columns,
+            ))
+        }
+    }
+
+    pub fn column(&'a self, column_index: usize) -> Option<Line<'a, T>> {
+        if column_index >= self.columns {
+            None
+        } else {
+            Some(Line::new(&self.data, column_index, self.columns, self.rows))
+        }
+    }
+
+    // The `rows` x `columns` part of the matrix whose top left corner is at (row, column)
+    pub fn window(
+        &'a self,
+        row: usize,
+        column: usize,
+        rows: usize,
+        columns: usize,
+    ) -> Option<View<'a, T>> {
+        if rows == 0 || columns == 0 || row + rows > self.rows || column + columns > self.columns {
+            None
+        } else {
+            Some(View::new(self, (row, column), rows, columns))
+        }
+    }
+
+    // Every `size` x `size` window, row by row
+    pub fn windows(&'a self, size: usize) -> Windows<'a, T> {
+        self.rectangular_windows(size, size)
+    }
+
+    pub fn rectangular_windows(&'a self, rows: usize, columns: usize) -> Windows<'a, T> {
+        Windows::new(self, rows, columns)
+    }
+
+    pub fn diagonal_count(&self) -> usize {
+        if self.data.is_empty() {
+            0
+        } else {
+            self.rows + self.columns - 1
+        }
+    }
+
+    // Left diagonals run down and to the right. They are numbered from the top right
+    // corner, along the top row to the left and then down the first column.
+    pub fn left_diagonal(&'a self, diagonal_index: usize) -> Option<Line<'a, T>> {
+        if diagonal_index >= self.diagonal_count() {
+            return None;
+        }
+
+        let starting_index = if diagonal_index < self.columns {
+            self.columns - diagonal_index - 1
+        } else {
+            (diagonal_index - (self.columns - 1)) * self.columns
+        };
+
+        self.left_diagonal_at_index(starting_index)
+    }
+
+    pub fn left_diagonal_at_index(&'a self, starting_index: usize) -> Option<Line<'a, T>> {
+        if starting_index >= self.data.len() {
+            None
+        } else {
+            let row = starting_index / self.columns;
+            let column = starting_index % self.columns;
+            let diagonal_length = (self.columns - column).min(self.rows - row);
+
+            Some(Line::new(
+                &self.data,
+                starting_index,
+                self.columns + 1,
+                diagonal_length,
+            ))
+        }
+    }
+
+    // Right diagonals run down and to the left. They are numbered from the top left
+    // corner, along the top row to the right and then down the last column.
+    pub fn right_diagonal(&'a self, diagonal_index: usize) -> Option<Line<'a, T>> {
+        if diagonal_index >= self.diagonal_count() {
+            return None;
+        }
+
+        let starting_index = if diagonal_index < self.columns {
+            diagonal_index
+        } else {
+            (self.columns - 1) + ((diagonal_index - (self.columns - 1)) * self.columns)
+        };
+
+        self.right_diagonal_at_index(starting_index)
+    }
+
+    pub fn right_diagonal_at_index(&'a self, starting_index: usize) -> Option<Line<'a, T>> {
+        if starting_index >= self.data.len() {
+            None
+        } else {
+            let row = starting_index / self.columns;
+            let column = starting_index % self.columns;
+            let diagonal_length = (column + 1).min(self.rows - row);
+
+            Some(Line::new(
+                &self.data,
+                starting_index,
+                self.columns - 1,
+                diagonal_length,
+            ))
+        }
+    }
+}
+
+impl<T: Default + Clone> Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+
+    fn index(&self, (row, column): (usize, usize)) -> &Self::Output {
+        self.get(row, column).unwrap_or_else(|| {
+            panic!(
+                "position ({}, {}) is outside of a {}x{} matrix",
+                row, column, self.rows, self.columns
+            )
+        })
+    }
+}
+
+impl<T: Default + Clone> IndexMut<(usize, usize)> for Matrix<T> {
+    fn index_mut(&mut self, (row, column): (usize, usize)) -> &mut Self::Output {
+        let (rows, columns) = (self.rows, self.columns);
+
+        self.get_mut(row, column).unwrap_or_else(|| {
+            panic!(
+                "position ({}, {}) is outside of a {}x{} matrix",
+                row, column, rows, columns
+            )
+        })
+    }
+}
+
+impl<T: Default + Clone + fmt::Display> fmt::Display for Matrix<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.data.chunks(self.columns.max(1)) {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cells hold their own linear index, so every line reads as a list of indices
+    fn matrix(rows: usize, columns: usize) -> Matrix<usize> {
+        Matrix::from_fn(rows, columns, |row, column| row * columns + column)
+    }
+
+    fn collect<'a>(
+        matrix: &'a Matrix<usize>,
+        line: impl Fn(&'a Matrix<usize>, usize) -> Option<Line<'a, usize>>,
+    ) -> Vec<Vec<usize>> {
+        (0..)
+            .map_while(|index| line(matrix, index))
+            .map(|line| line.copied().collect())
+            .collect()
+    }
+
+    #[test]
+    fn counts() {
+        let wide = matrix(2, 4);
+        assert_eq!(wide.row_count(), 2);
+        assert_eq!(wide.column_count(), 4);
+        assert_eq!(wide.diagonal_count(), 5);
+
+        let tall = matrix(4, 2);
+        assert_eq!(tall.row_count(), 4);
+        assert_eq!(tall.column_count(), 2);
+        assert_eq!(tall.diagonal_count(), 5);
+    }
+
+    #[test]
+    fn square() {
+        let m = matrix(3, 3);
+
+        assert_eq!(
+            collect(&m, Matrix::row),
+            vec![vec![0, 1, 2], vec![3, 4, 5], vec![6, 7, 8]]
+        );
+        assert_eq!(
+            collect(&m, Matrix::column),
+            vec![vec![0, 3, 6], vec![1, 4, 7], vec![2, 5, 8]]
+        );
+        assert_eq!(
+            collect(&m, Matrix::left_diagonal),
+            vec![vec![2], vec![1, 5], vec![0, 4, 8], vec![3, 7], vec![6]]
+        );
+        assert_eq!(
+            collect(&m, Matrix::right_diagonal),
+            vec![vec![0], vec![1, 3], vec![2, 4, 6], vec![5, 7], vec![8]]
+        );
+    }
+
+    #[test]
+    fn wide() {
+        let m = matrix(2, 4);
+
+        assert_eq!(
+            collect(&m, Matrix::row),
+            vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7]]
+        );
+        assert_eq!(
+            collect(&m, Matrix::column),
+            vec![vec![0, 4], vec![1, 5], vec![2, 6], vec![3, 7]]
+        );
+        assert_eq!(
+            collect(&m, Matrix::left_diagonal),
+            vec![vec![3], vec![2, 7], vec![1, 6], vec![0, 5], vec![4]]
+        );
+        assert_eq!(
+            collect(&m, Matrix::right_diagonal),
+            vec![vec![0], vec![1, 4], vec![2, 5], vec![3, 6], vec![7]]
+        );
+    }
+
+    #[test]
+    fn tall() {
+        let m = matrix(4, 2);
+
+        assert_eq!(
+            collect(&m, Matrix::row),
+            vec![vec![0, 1], vec![2, 3], vec![4, 5], vec![6, 7]]
+        );
+        assert_eq!(
+            collect(&m, Matrix::column),
+            vec![vec![0, 2, 4, 6], vec![1, 3, 5, 7]]
+        );
+        assert_eq!(
+            collect(&m, Matrix::left_diagonal),
+            vec![vec![1], vec![0, 3], vec![2, 5], vec![4, 7], vec![6]]
+        );
+        assert_eq!(
+            collect(&m, Matrix::right_diagonal),
+            vec![vec![0], vec![1, 2], vec![3, 4], vec![5, 6], vec![7]]
+        );
+    }
+
+    #[test]
+    fn single_row() {
+        let m = matrix(1, 3);
+
+        assert_eq!(collect(&m, Matrix::row), vec![vec![0, 1, 2]]);
+        assert_eq!(collect(&m, Matrix::column), vec![vec![0], vec![1], vec![2]]);
+        assert_eq!(
+            collect(&m, Matrix::left_diagonal),
+            vec![vec![2], vec![1], vec![0]]
+        );
+        assert_eq!(
+            collect(&m, Matrix::right_diagonal),
+            vec![vec![0], vec![1], vec![2]]
+        );
+    }
+
+    #[test]
+    fn single_column() {
+        let m = matrix(3, 1);
+
+        assert_eq!(collect(&m, Matrix::row), vec![vec![0], vec![1], vec![2]]);
+        assert_eq!(collect(&m, Matrix::column), vec![vec![0, 1, 2]]);
+        assert_eq!(
+            collect(&m, Matrix::left_diagonal),
+            vec![vec![0], vec![1], vec![2]]
+        );
+        assert_eq!(
+            collect(&m, Matrix::right_diagonal),
+            vec![vec![0], vec![1], vec![2]]
+        );
+    }
+
+    #[test]
+    fn diagonals_at_index() {
+        let m = matrix(2, 4);
+
+        let at = |line: Option<Line<usize>>| line.map(|line| line.copied().collect::<Vec<_>>());
+
+        assert_eq!(at(m.left_diagonal_at_index(1)), Some(vec![1, 6]));
+        assert_eq!(at(m.left_diagonal_at_index(5)), Some(vec![5]));
+        assert_eq!(at(m.left_diagonal_at_index(8)), None);
+        assert_eq!(at(m.right_diagonal_at_index(2)), Some(vec![2, 5]));
+        assert_eq!(at(m.right_diagonal_at_index(4)), Some(vec![4]));
+        assert_eq!(at(m.right_diagonal_at_index(8)), None);
+    }
+
+    #[test]
+    fn lines_are_exact_size_and_reversible() {
+        let m = matrix(3, 4);
+
+        let column = m.column(1).unwrap();
+        assert_eq!(column.len(), 3);
+        assert_eq!(column.rev().copied().collect::<Vec<_>>(), vec![9, 5, 1]);
+
+        let mut diagonal = m.left_diagonal(3).unwrap();
+        assert_eq!(diagonal.len(), 3);
+        assert_eq!(diagonal.next(), Some(&0));
+        assert_eq!(diagonal.next_back(), Some(&10));
+        assert_eq!(diagonal.len(), 1);
+        assert_eq!(diagonal.next(), Some(&5));
+        assert_eq!(diagonal.next_back(), None);
+
+        let row = m.row(2).unwrap();
+        assert_eq!(row.rev().copied().collect::<Vec<_>>(), vec![11, 10, 9, 8]);
+    }
+
+    #[test]
+    fn transforms() {
+        // 0 1 2
+        // 3 4 5
+        let m = matrix(2, 3);
+
+        let data = |m: Matrix<usize>| (m.row_count(), m.column_count(), m.data().clone());
+
+        assert_eq!(data(m.transpose()), (3, 2, vec![0, 3, 1, 4, 2, 5]));
+        assert_eq!(data(m.rotate_90()), (3, 2, vec![3, 0, 4, 1, 5, 2]));
+        assert_eq!(data(m.rotate_180()), (2, 3, vec![5, 4, 3, 2, 1, 0]));
+        assert_eq!(data(m.rotate_270()), (3, 2, vec![2, 5, 1, 4, 0, 3]));
+        assert_eq!(data(m.flip_horizontal()), (2, 3, vec![2, 1, 0, 5, 4, 3]));
+        assert_eq!(data(m.flip_vertical()), (2, 3, vec![3, 4, 5, 0, 1, 2]));
+
+        assert_eq!(m.rotate_90().rotate_270(), m);
+        assert_eq!(m.rotate_90().rotate_90(), m.rotate_180());
+        assert_eq!(m.transpose().transpose(), m);
+    }
+
+    #[test]
+    fn windows() {
+        let m = matrix(3, 4);
+
+        let window = m.window(1, 1, 2, 2).unwrap();
+        assert_eq!(window.origin(), (1, 1));
+        assert_eq!(window[(1, 0)], 9);
+        assert_eq!(window.get(2, 0), None);
+        assert_eq!(window.to_matrix().data(), &vec![5, 6, 9, 10]);
+        assert!(m.window(2, 0, 2, 2).is_none());
+
+        let origins: Vec<(usize, usize)> = m.windows(2).map(|view| view.origin()).collect();
+        assert_eq!(
+            origins,
+            vec![(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)]
+        );
+        assert_eq!(m.windows(4).count(), 0);
+        assert_eq!(m.rectangular_windows(1, 4).count(), 3);
+    }
+
+    #[test]
+    fn construction() {
+        assert!(Matrix::from_vec(2, 3, vec![0; 6]).is_ok());
+        assert_eq!(
+            Matrix::from_vec(2, 3, vec![0; 5]),
+            Err(MatrixError::SizeMismatch {
+                cells: 5,
+                rows: 2,
+                columns: 3
+            })
+        );
+        assert!(Matrix::from_vec(2, 3, vec![0; 7]).is_err());
+
+        let parsed = Matrix::parse("ab\ncd\n\n", |c| c).unwrap();
+        assert_eq!(parsed.row_count(), 2);
+        assert_eq!(parsed.column_count(), 2);
+        assert_eq!(parsed[(1, 0)], 'c');
+
+        assert_eq!(Matrix::parse("", |c| c), Err(MatrixError::Empty));
+        assert_eq!(Matrix::parse("\n\n", |c| c), Err(MatrixError::Empty));
+
+        let ragged = Matrix::parse("abc\nab\nabc", |c| c).unwrap_err();
+        assert_eq!(
+            ragged,
+            MatrixError::Ragged {
+                line: 2,
+                length: 2,
+                expected: 3
+            }
+        );
+        assert_eq!(
+            ragged.to_string(),
+            "line 2 has 2 cells, expected 3 like line 1"
+        );
+        assert!(matches!(
+            Matrix::parse("ab\n\nab", |c| c),
+            Err(MatrixError::Ragged { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn empty() {
+        let m: Matrix<usize> = Matrix::new(0, 0);
+
+        assert!(m.row(0).is_none());
+        assert!(m.column(0).is_none());
+        assert!(m.left_diagonal(0).is_none());
+        assert!(m.right_diagonal(0).is_none());
+        assert!(m.left_diagonal_at_index(0).is_none());
+        assert!(m.right_diagonal_at_index(0).is_none());
+        assert!(m.get(0, 0).is_none());
+        assert!(m.position(0).is_none());
+        assert_eq!(m.windows(1).count(), 0);
+    }
+
+    #[test]
+    fn out_of_range() {
+        let m = matrix(2, 4);
+
+        assert_eq!(m.position(7), Some((1, 3)));
+        assert!(m.position(8).is_none());
+        assert!(m.get(2, 0).is_none());
+        assert!(m.get(0, 4).is_none());
+
+        assert!(m.row(2).is_none());
+        assert!(m.column(4).is_none());
+        assert!(m.left_diagonal(5).is_none());
+        assert!(m.right_diagonal(5).is_none());
+    }
+
+    #[test]
+    fn get_and_index() {
+        let mut m = matrix(2, 3);
+
+        assert_eq!(m.get(1, 2), Some(&5));
+        assert_eq!(m[(0, 1)], 1);
+
+        *m.get_mut(1, 0).unwrap() = 30;
+        m[(0, 2)] = 20;
+        assert_eq!(m.data(), &vec![0, 1, 20, 30, 4, 5]);
+
+        assert!(m.get_mut(2, 0).is_none());
+        assert!(m.get_mut(0, 3).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "position (2, 0) is outside of a 2x3 matrix")]
+    fn index_out_of_range_panics() {
+        let m = matrix(2, 3);
+        let _ = m[(2, 0)];
+    }
+
+    #[test]
+    #[should_panic(expected = "position (0, 3) is outside of a 2x3 matrix")]
+    fn index_mut_out_of_range_panics() {
+        let mut m = matrix(2, 3);
+        m[(0, 3)] = 0;
+    }
+
+    #[test]
+    fn cells() {
+        let m = matrix(2, 3);
+
+        let cells: Vec<((usize, usize), usize)> = m
+            .cells()
+            .map(|(position, &cell)| (position, cell))
+            .collect();
+        assert_eq!(
+            cells,
+            vec![
+                ((0, 0), 0),
+                ((0, 1), 1),
+                ((0, 2), 2),
+                ((1, 0), 3),
+                ((1, 1), 4),
+                ((1, 2), 5)
+            ]
+        );
+    }
+
+    #[test]
+    fn neighbours() {
+        let m = matrix(3, 4);
+
+        let four = |row, column| m.neighbours4(row, column).collect::<Vec<_>>();
+        let eight = |row, column| m.neighbours8(row, column).collect::<Vec<_>>();
+
+        // Corners
+        assert_eq!(four(0, 0), vec![(0, 1), (1, 0)]);
+        assert_eq!(eight(0, 0), vec![(0, 1), (1, 1), (1, 0)]);
+        assert_eq!(four(2, 3), vec![(1, 3), (2, 2)]);
+        assert_eq!(eight(2, 3), vec![(1, 2), (1, 3), (2, 2)]);
+
+        // Edges
+        assert_eq!(four(0, 2), vec![(0, 3), (1, 2), (0, 1)]);
+        assert_eq!(eight(1, 3).len(), 5);
+
+        // Inside
+        assert_eq!(four(1, 1), vec![(0, 1), (1, 2), (2, 1), (1, 0)]);
+        assert_eq!(eight(1, 1).len(), 8);
+
+        // A single cell has no neighbours
+        let single = matrix(1, 1);
+        assert_eq!(single.neighbours8(0, 0).count(), 0);
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let m = Matrix::parse("#.\n.#\n", |c| c == '#').unwrap();
+
+        assert_eq!(m.data(), &vec![true, false, false, true]);
+
+        let text = Matrix::parse("ab\ncd", |c| c).unwrap().to_string();
+        assert_eq!(text, "ab\ncd\n");
+        assert_eq!(Matrix::parse(&text, |c| c).unwrap().to_string(), text);
+    }
+}
@@ -0,0 +1,234 @@
+use std::io::{self, Write};
+
+use crate::Matrix;
+
+pub type Rgb = [u8; 3];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Ppm,
+    Png,
+}
+
+impl ImageFormat {
+    // Picks the format from the file extension, defaulting to PNG
+    pub fn from_path(path: &str) -> Self {
+        if path.to_ascii_lowercase().ends_with(".ppm") {
+            ImageFormat::Ppm
+        } else {
+            ImageFormat::Png
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+
+impl<T: Default + Clone> Matrix<T> {
+    pub fn render_text(&self, out: &mut impl Write, symbol: impl Fn(&T) -> char) -> io::Result<()> {
+        self.render_ansi(out, symbol, |_, _| None)
+    }
+
+    // Cells for which `highlight` returns a colour are drawn on that background
+    pub fn render_ansi(
+        &self,
+        out: &mut impl Write,
+        symbol: impl Fn(&T) -> char,
+        highlight: impl Fn((usize, usize), &T) -> Option<Rgb>,
+    ) -> io::Result<()> {
+        for row in 0..self.row_count() {
+            for column in 0..self.column_count() {
+                let cell = &self[(row, column)];
+
+                match highlight((row, column), cell) {
+                    Some([r, g, b]) => {
+                        write!(out, "\x1b[48;2;{};{};{}m{}{}", r, g, b, symbol(cell), RESET)?
+                    }
+                    None => write!(out, "{}", symbol(cell))?,
+                }
+            }
+            writeln!(out)?;
+        }
+
+        Ok(())
+    }
+
+    // Every cell becomes a `scale` x `scale` square of pixels
+    pub fn render_image(
+        &self,
+        out: &mut impl Write,
+        format: ImageFormat,
+        scale: usize,
+        colour: impl Fn((usize, usize), &T) -> Rgb,
+    ) -> io::Result<()> {
+        let scale = scale.max(1);
+        let (width, height) = (self.column_count() * scale, self.row_count() * scale);
+
+        let rows = (0..height).map(|y| {
+            (0..width)
+                .flat_map(|x| colour((y / scale, x / scale), &self[(y / scale, x / scale)]))
+                .collect::<Vec<u8>>()
+        });
+
+        match format {
+            ImageFormat::Ppm => {
+                write!(out, "P6\n{} {}\n255\n", width, height)?;
+                for row in rows {
+                    out.write_all(&row)?;
+                }
+                Ok(())
+            }
+            ImageFormat::Png => write_png(out, width, height, rows),
+        }
+    }
+}
+
+fn write_png(
+    out: &mut impl Write,
+    width: usize,
+    height: usize,
+    rows: impl Iterator<Item = Vec<u8>>,
+) -> io::Result<()> {
+    let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "image is too large for PNG");
+    let width = u32::try_from(width).map_err(|_| too_large())?;
+    let height = u32::try_from(height).map_err(|_| too_large())?;
+
+    out.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    // 8 bits per channel, truecolour, no interlacing
+    header.extend([8, 2, 0, 0, 0]);
+    write_chunk(out, b"IHDR", &header)?;
+
+    // Each scanline starts with filter type 0 (none)
+    let mut raw = Vec::new();
+    for row in rows {
+        raw.push(0);
+        raw.extend(row);
+    }
+    write_chunk(out, b"IDAT", &zlib_stored(&raw))?;
+
+    write_chunk(out, b"IEND", &[])
+}
+
+fn write_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+
+    let crc = crc32(kind.iter().chain(data));
+    out.write_all(&crc.to_be_bytes())
+}
+
+// A zlib stream made of uncompressed deflate blocks, which every PNG decoder
+// accepts and which needs no compressor
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = u16::MAX as usize;
+
+    let mut out = vec![0x78, 0x01];
+
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+
+        out.push(last as u8);
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend(block);
+    }
+
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MODULUS: u32 = 65521;
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % MODULUS;
+        b = (b + a) % MODULUS;
+    }
+
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"IEND".iter()), 0xae42_6082);
+        assert_eq!(crc32(b"123456789".iter()), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn stored_blocks() {
+        let data = vec![7u8; 70_000];
+        let stream = zlib_stored(&data);
+
+        // Header, two block headers, the data itself and the checksum
+        assert_eq!(stream.len(), 2 + 5 + 5 + data.len() + 4);
+        assert_eq!(&stream[2..7], &[0, 0xff, 0xff, 0, 0]);
+        assert_eq!(stream[7 + 65535], 1);
+    }
+
+    #[test]
+    fn text_and_images() {
+        let m = Matrix::parse("ab\ncd", |c| c).unwrap();
+
+        let mut text = Vec::new();
+        m.render_text(&mut text, |&c| c).unwrap();
+        assert_eq!(text, b"ab\ncd\n");
+
+        let mut ansi = Vec::new();
+        m.render_ansi(
+            &mut ansi,
+            |&c| c,
+            |position, _| (position == (1, 1)).then_some([255, 0, 0]),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(ansi).unwrap(),
+            "ab\nc\x1b[48;2;255;0;0md\x1b[0m\n"
+        );
+
+        let mut ppm = Vec::new();
+        m.render_image(&mut ppm, ImageFormat::Ppm, 2, |_, &c| [c as u8, 0, 0])
+            .unwrap();
+        assert!(ppm.starts_with(b"P6\n4 4\n255\n"));
+        assert_eq!(ppm.len(), 11 + 4 * 4 * 3);
+        assert_eq!(&ppm[11..14], &[b'a', 0, 0]);
+        assert_eq!(&ppm[ppm.len() - 3..], &[b'd', 0, 0]);
+
+        let mut png = Vec::new();
+        m.render_image(&mut png, ImageFormat::Png, 1, |_, _| [0, 0, 0])
+            .unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\0\x02\0\0\0\x02"));
+        assert!(png.ends_with(b"\0\0\0\0IEND\xae\x42\x60\x82"));
+    }
+}
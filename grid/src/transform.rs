@@ -0,0 +1,61 @@
+use crate::Matrix;
+
+impl<T: Default + Clone> Matrix<T> {
+    // Builds a `rows` x `columns` matrix where each cell is taken from `source`'s
+    // (row, column) position
+    fn remap(
+        &self,
+        rows: usize,
+        columns: usize,
+        source: impl Fn(usize, usize) -> (usize, usize),
+    ) -> Self {
+        Matrix::from_fn(rows, columns, |row, column| {
+            self[source(row, column)].clone()
+        })
+    }
+
+    pub fn transpose(&self) -> Self {
+        self.remap(self.column_count(), self.row_count(), |row, column| {
+            (column, row)
+        })
+    }
+
+    // Quarter turn clockwise
+    pub fn rotate_90(&self) -> Self {
+        let rows = self.row_count();
+        self.remap(self.column_count(), rows, |row, column| {
+            (rows - 1 - column, row)
+        })
+    }
+
+    pub fn rotate_180(&self) -> Self {
+        let (rows, columns) = (self.row_count(), self.column_count());
+        self.remap(rows, columns, |row, column| {
+            (rows - 1 - row, columns - 1 - column)
+        })
+    }
+
+    // Quarter turn counterclockwise
+    pub fn rotate_270(&self) -> Self {
+        let columns = self.column_count();
+        self.remap(columns, self.row_count(), |row, column| {
+            (column, columns - 1 - row)
+        })
+    }
+
+    // Mirror image left to right
+    pub fn flip_horizontal(&self) -> Self {
+        let columns = self.column_count();
+        self.remap(self.row_count(), columns, |row, column| {
+            (row, columns - 1 - column)
+        })
+    }
+
+    // Mirror image top to bottom
+    pub fn flip_vertical(&self) -> Self {
+        let rows = self.row_count();
+        self.remap(rows, self.column_count(), |row, column| {
+            (rows - 1 - row, column)
+        })
+    }
+}
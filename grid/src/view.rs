@@ -0,0 +1,127 @@
+use std::ops::Index;
+
+use crate::{Line, Matrix};
+
+// A borrowed rectangular part of a matrix. Positions are relative to its top left
+// corner, which sits at `origin` in the matrix.
+#[derive(Debug, Clone, Copy)]
+pub struct View<'a, T: Default + Clone> {
+    matrix: &'a Matrix<T>,
+    origin: (usize, usize),
+    rows: usize,
+    columns: usize,
+}
+
+impl<'a, T: Default + Clone> View<'a, T> {
+    pub(crate) fn new(
+        matrix: &'a Matrix<T>,
+        origin: (usize, usize),
+        rows: usize,
+        columns: usize,
+    ) -> Self {
+        Self {
+            matrix,
+            origin,
+            rows,
+            columns,
+        }
+    }
+
+    pub fn origin(&self) -> (usize, usize) {
+        self.origin
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.columns
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> Option<&'a T> {
+        if row < self.rows && column < self.columns {
+            self.matrix.get(self.origin.0 + row, self.origin.1 + column)
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, row_index: usize) -> Option<Line<'a, T>> {
+        if row_index >= self.rows {
+            None
+        } else {
+            let start = (self.origin.0 + row_index) * self.matrix.column_count() + self.origin.1;
+
+            Some(Line::new(self.matrix.data(), start, 1, self.columns))
+        }
+    }
+
+    // Every cell together with its (row, column) inside the view
+    pub fn cells(&self) -> impl Iterator<Item = ((usize, usize), &'a T)> + '_ {
+        (0..self.rows).flat_map(move |row| {
+            self.row(row)
+                .into_iter()
+                .flatten()
+                .enumerate()
+                .map(move |(column, cell)| ((row, column), cell))
+        })
+    }
+
+    pub fn to_matrix(&self) -> Matrix<T> {
+        Matrix::from_fn(self.rows, self.columns, |row, column| {
+            self[(row, column)].clone()
+        })
+    }
+}
+
+impl<T: Default + Clone> Index<(usize, usize)> for View<'_, T> {
+    type Output = T;
+
+    fn index(&self, (row, column): (usize, usize)) -> &Self::Output {
+        self.get(row, column).unwrap_or_else(|| {
+            panic!(
+                "position ({}, {}) is outside of a {}x{} view",
+                row, column, self.rows, self.columns
+            )
+        })
+    }
+}
+
+// Every `rows` x `columns` view of a matrix, row by row
+#[derive(Debug, Clone)]
+pub struct Windows<'a, T: Default + Clone> {
+    matrix: &'a Matrix<T>,
+    rows: usize,
+    columns: usize,
+    next: (usize, usize),
+}
+
+impl<'a, T: Default + Clone> Windows<'a, T> {
+    pub(crate) fn new(matrix: &'a Matrix<T>, rows: usize, columns: usize) -> Self {
+        Self {
+            matrix,
+            rows,
+            columns,
+            next: (0, 0),
+        }
+    }
+}
+
+impl<'a, T: Default + Clone> Iterator for Windows<'a, T> {
+    type Item = View<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (row, column) = self.next;
+
+        let view = self.matrix.window(row, column, self.rows, self.columns)?;
+
+        self.next = if column + self.columns < self.matrix.column_count() {
+            (row, column + 1)
+        } else {
+            (row + 1, 0)
+        };
+
+        Some(view)
+    }
+}
@@ -0,0 +1,274 @@
+mod pattern;
+mod scanner;
+mod stream;
+
+use std::{io::Read, ops::Range};
+
+pub use pattern::{Pattern, Token};
+pub use scanner::Scanner;
+pub use stream::Stream;
+
+use pattern::Atom;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spanned<T> {
+    pub value: T,
+    // Byte range of the match in the scanned input
+    pub span: Range<usize>,
+}
+
+// When the scanner starts a new attempt at every pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Restart {
+    // On every byte, so a partial match never hides a match that starts inside it
+    EveryByte,
+    // Only once the previous attempt is over. A failed attempt also takes the byte
+    // it failed on, as the original day3 parser did
+    AfterAttempt,
+}
+
+#[derive(Debug)]
+pub struct Matcher<T> {
+    // Every pattern is compiled into one or more flat atom sequences, one per
+    // combination of alternation branches
+    sequences: Vec<(Vec<Atom>, usize)>,
+    builders: Vec<fn(&[i64]) -> T>,
+    restart: Restart,
+}
+
+impl<T> Matcher<T> {
+    pub fn new(patterns: Vec<Pattern<T>>) -> Self {
+        let mut sequences = Vec::new();
+        let mut builders = Vec::new();
+
+        for (index, pattern) in patterns.into_iter().enumerate() {
+            for atoms in pattern::compile(&pattern.tokens) {
+                sequences.push((atoms, index));
+            }
+            builders.push(pattern.build);
+        }
+
+        Self {
+            sequences,
+            builders,
+            restart: Restart::EveryByte,
+        }
+    }
+
+    pub fn with_restart(mut self, restart: Restart) -> Self {
+        self.restart = restart;
+        self
+    }
+
+    pub fn scanner(&self) -> Scanner<'_, T> {
+        Scanner::new(self)
+    }
+
+    pub fn stream<R: Read>(&self, reader: R) -> Stream<'_, R, T> {
+        Stream::new(self.scanner(), reader)
+    }
+
+    pub fn find_all(&self, input: &[u8]) -> Vec<Spanned<T>> {
+        let mut scanner = self.scanner();
+
+        let mut out: Vec<Spanned<T>> = input.iter().flat_map(|&byte| scanner.feed(byte)).collect();
+        out.extend(scanner.finish());
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(text: &'static str) -> Pattern<String> {
+        Pattern::new(vec![Token::Literal(text)], |_| String::new())
+    }
+
+    fn number(max_digits: usize) -> Token {
+        Token::Number {
+            max_digits,
+            signed: false,
+        }
+    }
+
+    fn spans<T>(matches: Vec<Spanned<T>>) -> Vec<Range<usize>> {
+        matches.into_iter().map(|found| found.span).collect()
+    }
+
+    #[test]
+    fn literals() {
+        let matcher = Matcher::new(vec![literal("ab")]);
+
+        assert_eq!(
+            spans(matcher.find_all(b"xabyaabab")),
+            vec![1..3, 5..7, 7..9]
+        );
+        assert!(matcher.find_all(b"a\xffb").is_empty());
+    }
+
+    #[test]
+    fn caseless_literals() {
+        let matcher = Matcher::new(vec![Pattern::new(
+            vec![Token::CaselessLiteral("ab")],
+            |_| (),
+        )]);
+
+        assert_eq!(spans(matcher.find_all(b"AB aB ab")), vec![0..2, 3..5, 6..8]);
+    }
+
+    #[test]
+    fn max_digits_cut_off() {
+        let matcher = Matcher::new(vec![Pattern::new(
+            vec![Token::Literal("("), number(3), Token::Literal(")")],
+            |captures: &[i64]| captures[0],
+        )]);
+
+        let values = |input: &[u8]| -> Vec<i64> {
+            matcher
+                .find_all(input)
+                .into_iter()
+                .map(|found| found.value)
+                .collect()
+        };
+
+        assert_eq!(values(b"(1)(12)(123)"), vec![1, 12, 123]);
+        assert!(values(b"(1234)()").is_empty());
+    }
+
+    #[test]
+    fn signed_numbers() {
+        let matcher = Matcher::new(vec![Pattern::new(
+            vec![
+                Token::Literal("="),
+                Token::Number {
+                    max_digits: 3,
+                    signed: true,
+                },
+                Token::Literal(";"),
+            ],
+            |captures: &[i64]| captures[0],
+        )]);
+
+        let values: Vec<i64> = matcher
+            .find_all(b"=-12;=+3;=4;=-;=+-1;")
+            .into_iter()
+            .map(|found| found.value)
+            .collect();
+        assert_eq!(values, vec![-12, 3, 4]);
+    }
+
+    #[test]
+    fn trailing_atoms_close_at_end_of_input() {
+        let matcher = Matcher::new(vec![Pattern::new(
+            vec![Token::Literal("n"), number(3)],
+            |captures: &[i64]| captures[0],
+        )]);
+
+        let found = matcher.find_all(b"n12");
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].value, found[0].span.clone()), (12, 0..3));
+
+        let matcher = Matcher::new(vec![Pattern::new(
+            vec![Token::Literal("a"), Token::Whitespace],
+            |_| (),
+        )]);
+
+        assert_eq!(spans(matcher.find_all(b"a  ")), vec![0..3]);
+        assert_eq!(spans(matcher.find_all(b"a")), vec![0..1]);
+    }
+
+    #[test]
+    fn alternation_expands_every_branch() {
+        let matcher = Matcher::new(vec![Pattern::new(
+            vec![
+                Token::Alternation(vec![vec![Token::Literal("a")], vec![Token::Literal("bb")]]),
+                Token::Alternation(vec![vec![Token::Literal("c")], vec![number(1)]]),
+                Token::Literal("!"),
+            ],
+            |captures: &[i64]| captures.to_vec(),
+        )]);
+
+        let found = matcher.find_all(b"ac! bbc! a7! bb7! ab!");
+        assert_eq!(spans(found.clone()), vec![0..3, 4..8, 9..12, 13..17]);
+        assert_eq!(
+            found
+                .into_iter()
+                .map(|found| found.value)
+                .collect::<Vec<_>>(),
+            vec![vec![], vec![], vec![7], vec![7]]
+        );
+    }
+
+    #[test]
+    fn leftmost_match_wins_over_earlier_completion() {
+        let matcher = Matcher::new(vec![literal("abcd"), literal("bc")]);
+
+        assert_eq!(spans(matcher.find_all(b"abcd")), vec![0..4]);
+        assert_eq!(spans(matcher.find_all(b"abcx")), vec![1..3]);
+        assert_eq!(spans(matcher.find_all(b"abc")), vec![1..3]);
+    }
+
+    #[test]
+    fn matches_completing_on_the_same_byte() {
+        // The number only ends on the "x", which completes the second pattern too
+        let matcher = Matcher::new(vec![
+            Pattern::new(vec![Token::Literal("n"), number(3)], |_| 0),
+            Pattern::new(vec![Token::Literal("x")], |_| 1),
+        ]);
+
+        assert_eq!(spans(matcher.find_all(b"n12x")), vec![0..3, 3..4]);
+    }
+
+    #[test]
+    fn failed_attempts_take_their_last_byte() {
+        let matcher = Matcher::new(vec![literal("ab"), literal("abcd"), literal("bc")])
+            .with_restart(Restart::AfterAttempt);
+
+        assert_eq!(spans(matcher.find_all(b"aab")), Vec::<Range<usize>>::new());
+        assert_eq!(spans(matcher.find_all(b"axab")), vec![2..4]);
+        // The first pattern to complete ends the attempt
+        assert_eq!(spans(matcher.find_all(b"abcd")), vec![0..2]);
+        assert_eq!(spans(matcher.find_all(b"abbc")), vec![0..2, 2..4]);
+    }
+
+    #[test]
+    fn byte_after_a_trailing_atom_starts_the_next_attempt() {
+        let matcher = Matcher::new(vec![
+            Pattern::new(vec![Token::Literal("n"), number(3)], |_| 0),
+            Pattern::new(vec![Token::Literal("x")], |_| 1),
+        ])
+        .with_restart(Restart::AfterAttempt);
+
+        assert_eq!(spans(matcher.find_all(b"n12n3")), vec![0..3, 3..5]);
+        assert_eq!(spans(matcher.find_all(b"n12x")), vec![0..3, 3..4]);
+        assert_eq!(spans(matcher.find_all(b"nnx")), vec![2..3]);
+    }
+
+    #[test]
+    fn streaming_matches_scanning_at_once() {
+        let input = b"abcdxbcdabcabcd";
+
+        for restart in [Restart::EveryByte, Restart::AfterAttempt] {
+            let matcher = Matcher::new(vec![literal("abcd"), literal("bc"), literal("cd")])
+                .with_restart(restart);
+
+            for chunk_size in 1..=input.len() {
+                let streamed: Vec<Spanned<String>> = matcher
+                    .stream(&input[..])
+                    .with_chunk_size(chunk_size)
+                    .map(Result::unwrap)
+                    .collect();
+
+                assert_eq!(
+                    streamed,
+                    matcher.find_all(input),
+                    "{:?}, chunk size {}",
+                    restart,
+                    chunk_size
+                );
+            }
+        }
+    }
+}
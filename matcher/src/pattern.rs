@@ -0,0 +1,72 @@
+#[derive(Debug, Clone)]
+pub enum Token {
+    Literal(&'static str),
+    // Literal that also matches with any ASCII letters in a different case
+    CaselessLiteral(&'static str),
+    // Number of 1 to `max_digits` digits, optionally preceded by `+` or `-` when
+    // `signed`, captured for the builder
+    Number { max_digits: usize, signed: bool },
+    // Any run of whitespace, including none at all
+    Whitespace,
+    Alternation(Vec<Vec<Token>>),
+}
+
+pub struct Pattern<T> {
+    pub(crate) tokens: Vec<Token>,
+    pub(crate) build: fn(&[i64]) -> T,
+}
+
+impl<T> Pattern<T> {
+    pub fn new(tokens: Vec<Token>, build: fn(&[i64]) -> T) -> Self {
+        Self { tokens, build }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Atom {
+    Byte(u8),
+    CaselessByte(u8),
+    Number { max_digits: usize, signed: bool },
+    Whitespace,
+}
+
+pub(crate) fn compile(tokens: &[Token]) -> Vec<Vec<Atom>> {
+    let mut sequences: Vec<Vec<Atom>> = vec![Vec::new()];
+
+    for token in tokens {
+        match token {
+            Token::Literal(literal) => sequences
+                .iter_mut()
+                .for_each(|sequence| sequence.extend(literal.bytes().map(Atom::Byte))),
+            Token::CaselessLiteral(literal) => sequences
+                .iter_mut()
+                .for_each(|sequence| sequence.extend(literal.bytes().map(Atom::CaselessByte))),
+            Token::Number { max_digits, signed } => sequences.iter_mut().for_each(|sequence| {
+                sequence.push(Atom::Number {
+                    max_digits: *max_digits,
+                    signed: *signed,
+                })
+            }),
+            Token::Whitespace => sequences
+                .iter_mut()
+                .for_each(|sequence| sequence.push(Atom::Whitespace)),
+            Token::Alternation(branches) => {
+                let suffixes: Vec<Vec<Atom>> =
+                    branches.iter().flat_map(|branch| compile(branch)).collect();
+
+                sequences = sequences
+                    .iter()
+                    .flat_map(|prefix| {
+                        suffixes.iter().map(move |suffix| {
+                            let mut sequence = prefix.clone();
+                            sequence.extend(suffix);
+                            sequence
+                        })
+                    })
+                    .collect();
+            }
+        }
+    }
+
+    sequences
+}
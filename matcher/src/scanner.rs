@@ -0,0 +1,209 @@
+use crate::{pattern::Atom, Matcher, Restart, Spanned};
+
+#[derive(Debug, Clone)]
+struct Thread {
+    sequence: usize,
+    start: usize,
+    atom: usize,
+    digits: usize,
+    sign: Option<i64>,
+    value: i64,
+    captures: Vec<i64>,
+}
+
+enum Step {
+    Consumed,
+    Dead,
+}
+
+impl Thread {
+    fn new(sequence: usize, start: usize) -> Self {
+        Self {
+            sequence,
+            start,
+            atom: 0,
+            digits: 0,
+            sign: None,
+            value: 0,
+            captures: Vec::new(),
+        }
+    }
+
+    // `None` marks the end of the input, which can only close pending atoms
+    fn step(&mut self, atoms: &[Atom], byte: Option<u8>) -> Step {
+        while let Some(&atom) = atoms.get(self.atom) {
+            match atom {
+                Atom::Byte(expected) => {
+                    return if byte == Some(expected) {
+                        self.atom += 1;
+                        Step::Consumed
+                    } else {
+                        Step::Dead
+                    };
+                }
+                Atom::CaselessByte(expected) => {
+                    return match byte {
+                        Some(byte) if byte.eq_ignore_ascii_case(&expected) => {
+                            self.atom += 1;
+                            Step::Consumed
+                        }
+                        _ => Step::Dead,
+                    };
+                }
+                Atom::Number { max_digits, signed } => match byte {
+                    Some(sign @ (b'+' | b'-'))
+                        if signed && self.digits == 0 && self.sign.is_none() =>
+                    {
+                        self.sign = Some(if sign == b'-' { -1 } else { 1 });
+                        return Step::Consumed;
+                    }
+                    Some(digit) if digit.is_ascii_digit() && self.digits < max_digits => {
+                        self.digits += 1;
+                        // Saturates rather than overflows on absurdly wide numbers
+                        self.value = self
+                            .value
+                            .saturating_mul(10)
+                            .saturating_add((digit - b'0') as i64);
+                        return Step::Consumed;
+                    }
+                    _ if self.digits > 0 => {
+                        self.captures.push(self.sign.unwrap_or(1) * self.value);
+                        self.digits = 0;
+                        self.sign = None;
+                        self.value = 0;
+                        self.atom += 1;
+                    }
+                    _ => return Step::Dead,
+                },
+                Atom::Whitespace => match byte {
+                    Some(byte) if byte.is_ascii_whitespace() => return Step::Consumed,
+                    _ => self.atom += 1,
+                },
+            }
+        }
+
+        Step::Dead
+    }
+
+    fn is_complete(&self, atoms: &[Atom]) -> bool {
+        self.atom == atoms.len()
+    }
+}
+
+// Runs every pattern in parallel over the input, one byte at a time, so the input
+// is read exactly once and never backtracked over. A new attempt at every pattern
+// starts on every byte. The input does not have to be valid UTF-8: anything that is
+// not part of a pattern is simply skipped.
+//
+// Matches never overlap. The leftmost one wins, and among matches that start on the
+// same byte the one that completes first wins, then the one from the earlier
+// pattern. A completed match is therefore held back while an attempt that started
+// before it is still running. With `Restart::AfterAttempt` only one attempt runs at
+// a time, so nothing is ever held back.
+pub struct Scanner<'m, T> {
+    matcher: &'m Matcher<T>,
+    threads: Vec<Thread>,
+    // Completed threads that are not emitted yet, with the end of their match
+    completed: Vec<(Thread, usize)>,
+    position: usize,
+}
+
+impl<'m, T> Scanner<'m, T> {
+    pub(crate) fn new(matcher: &'m Matcher<T>) -> Self {
+        Self {
+            matcher,
+            threads: Vec::new(),
+            completed: Vec::new(),
+            position: 0,
+        }
+    }
+
+    pub fn feed(&mut self, byte: u8) -> Vec<Spanned<T>> {
+        let idle = self.threads.is_empty();
+        if self.matcher.restart == Restart::EveryByte || idle {
+            self.spawn();
+        }
+
+        let mut out = self.advance(Some(byte));
+
+        // A match that ended on the byte before leaves this one free to start the next
+        if self.matcher.restart == Restart::AfterAttempt
+            && !idle
+            && self.threads.is_empty()
+            && out
+                .last()
+                .is_some_and(|found| found.span.end == self.position)
+        {
+            self.spawn();
+            out.extend(self.advance(Some(byte)));
+        }
+
+        self.position += 1;
+
+        out
+    }
+
+    fn spawn(&mut self) {
+        let start = self.position;
+        self.threads
+            .extend((0..self.matcher.sequences.len()).map(|sequence| Thread::new(sequence, start)));
+    }
+
+    pub fn finish(&mut self) -> Vec<Spanned<T>> {
+        self.advance(None)
+    }
+
+    fn advance(&mut self, byte: Option<u8>) -> Vec<Spanned<T>> {
+        let sequences = &self.matcher.sequences;
+        let position = self.position;
+        let completed = &mut self.completed;
+
+        // Threads are kept in spawn order, so completions are recorded in the order
+        // that breaks ties between matches with the same start
+        self.threads.retain_mut(|thread| {
+            let atoms = &sequences[thread.sequence].0;
+
+            match thread.step(atoms, byte) {
+                Step::Consumed if thread.is_complete(atoms) => {
+                    completed.push((thread.clone(), position + 1));
+                    false
+                }
+                Step::Consumed => true,
+                Step::Dead => {
+                    // Trailing numbers and whitespace only end on the byte after them
+                    if thread.is_complete(atoms) {
+                        completed.push((thread.clone(), position));
+                    }
+                    false
+                }
+            }
+        });
+
+        let mut out = Vec::new();
+
+        while let Some(index) =
+            (0..self.completed.len()).min_by_key(|&index| (self.completed[index].0.start, index))
+        {
+            let start = self.completed[index].0.start;
+
+            // An attempt that started earlier may still become the leftmost match
+            if self.threads.iter().any(|thread| thread.start < start) {
+                break;
+            }
+
+            let (thread, end) = self.completed.remove(index);
+
+            // Matches never overlap, so drop everything that started inside this one
+            self.threads.retain(|thread| thread.start >= end);
+            self.completed.retain(|(thread, _)| thread.start >= end);
+
+            let (_, pattern) = sequences[thread.sequence];
+            out.push(Spanned {
+                value: (self.matcher.builders[pattern])(&thread.captures),
+                span: thread.start..end,
+            });
+        }
+
+        out
+    }
+}
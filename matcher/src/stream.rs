@@ -0,0 +1,82 @@
+use std::{
+    collections::VecDeque,
+    io::{self, ErrorKind, Read},
+};
+
+use crate::{Scanner, Spanned};
+
+const DEFAULT_CHUNK_SIZE: usize = 8 * 1024;
+
+// Reads the input in fixed-size chunks and yields matches as they complete. The
+// scanner keeps its state between chunks, so a match may straddle a chunk boundary.
+pub struct Stream<'m, R, T> {
+    scanner: Scanner<'m, T>,
+    reader: R,
+    buffer: Vec<u8>,
+    // Matches completed by the last byte fed, not yielded yet
+    pending: VecDeque<Spanned<T>>,
+    filled: usize,
+    offset: usize,
+    finished: bool,
+}
+
+impl<'m, R: Read, T> Stream<'m, R, T> {
+    pub(crate) fn new(scanner: Scanner<'m, T>, reader: R) -> Self {
+        Self {
+            scanner,
+            reader,
+            buffer: vec![0; DEFAULT_CHUNK_SIZE],
+            pending: VecDeque::new(),
+            filled: 0,
+            offset: 0,
+            finished: false,
+        }
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be positive");
+
+        self.buffer = vec![0; chunk_size];
+        self
+    }
+}
+
+impl<R: Read, T> Iterator for Stream<'_, R, T> {
+    type Item = io::Result<Spanned<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(out) = self.pending.pop_front() {
+                return Some(Ok(out));
+            }
+
+            if self.offset < self.filled {
+                let byte = self.buffer[self.offset];
+                self.offset += 1;
+
+                self.pending.extend(self.scanner.feed(byte));
+                continue;
+            }
+
+            if self.finished {
+                return None;
+            }
+
+            match self.reader.read(&mut self.buffer) {
+                Ok(0) => {
+                    self.finished = true;
+                    self.pending.extend(self.scanner.finish());
+                }
+                Ok(n) => {
+                    self.filled = n;
+                    self.offset = 0;
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => {
+                    self.finished = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}